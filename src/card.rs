@@ -1,6 +1,9 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, str::FromStr};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Suit {
     Clubs,
     Diamonds,
@@ -19,6 +22,21 @@ impl fmt::Display for Suit {
     }
 }
 
+// a letter in either case, or the symbol Display prints
+impl FromStr for Suit {
+    type Err = CardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "c" | "C" | "♣" => Ok(Suit::Clubs),
+            "d" | "D" | "♦" => Ok(Suit::Diamonds),
+            "h" | "H" | "♥" => Ok(Suit::Hearts),
+            "s" | "S" | "♠" => Ok(Suit::Spades),
+            _ => Err(CardError::ParseSuitError),
+        }
+    }
+}
+
 impl TryFrom<u8> for Suit {
     type Error = CardError;
 
@@ -34,6 +52,7 @@ impl TryFrom<u8> for Suit {
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Rank {
     Ace,
     Two,
@@ -70,6 +89,29 @@ impl fmt::Display for Rank {
     }
 }
 
+impl FromStr for Rank {
+    type Err = CardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "A" | "a" => Ok(Rank::Ace),
+            "2" => Ok(Rank::Two),
+            "3" => Ok(Rank::Three),
+            "4" => Ok(Rank::Four),
+            "5" => Ok(Rank::Five),
+            "6" => Ok(Rank::Six),
+            "7" => Ok(Rank::Seven),
+            "8" => Ok(Rank::Eight),
+            "9" => Ok(Rank::Nine),
+            "T" | "t" | "10" => Ok(Rank::Ten),
+            "J" | "j" => Ok(Rank::Jack),
+            "Q" | "q" => Ok(Rank::Queen),
+            "K" | "k" => Ok(Rank::King),
+            _ => Err(CardError::ParseRankError),
+        }
+    }
+}
+
 impl TryFrom<u8> for Rank {
     type Error = CardError;
 
@@ -94,6 +136,7 @@ impl TryFrom<u8> for Rank {
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Card {
     suit: Suit,
     rank: Rank,
@@ -119,6 +162,36 @@ impl fmt::Display for Card {
     }
 }
 
+// rank then suit, as in "As", "Td" or "10d"; suit first, as Display prints "♠A", also parses
+impl FromStr for Card {
+    type Err = CardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let first: char = s.chars().next().ok_or(CardError::ParseCardError)?;
+
+        // no rank starts with a suit letter or symbol
+        let (rank, suit) = if first.to_string().parse::<Suit>().is_ok() {
+            let (suit, rank) = s.split_at(first.len_utf8());
+            (rank, suit)
+        } else {
+            s.split_at(s.char_indices().last().ok_or(CardError::ParseCardError)?.0)
+        };
+
+        Ok(Self { suit: suit.parse()?, rank: rank.parse()? })
+    }
+}
+
+// cards in the given order from a string like "As Td 5h"; panics on a malformed card, so for tests and scenarios
+#[macro_export]
+macro_rules! cards {
+    ($cards:expr) => {
+        $cards
+            .split_whitespace()
+            .map(|card| card.parse::<$crate::card::Card>().expect("malformed card"))
+            .collect::<Vec<$crate::card::Card>>()
+    };
+}
+
 impl TryFrom<u8> for Card {
     type Error = CardError;
 
@@ -186,6 +259,25 @@ mod tests {
         assert_eq!(Err(CardError::ParseRankError), Rank::try_from(13u8))
     }
 
+    #[test]
+    fn parse_cards_from_str() {
+        assert_eq!(Ok(Card::new(Suit::Spades, Rank::Ace)), "As".parse());
+        assert_eq!(Ok(Card::new(Suit::Diamonds, Rank::Ten)), "10d".parse());
+        assert_eq!(Ok(Card::new(Suit::Clubs, Rank::King)), "KC".parse());
+
+        assert_eq!(Err(CardError::ParseCardError), "".parse::<Card>());
+        assert_eq!(Err(CardError::ParseSuitError), "Ax".parse::<Card>());
+        assert_eq!(Err(CardError::ParseRankError), "1s".parse::<Card>());
+
+        assert_eq!(vec![Card::new(Suit::Spades, Rank::Ace), Card::new(Suit::Diamonds, Rank::Ten)], crate::cards!("As Td"));
+
+        assert_eq!(Ok(Card::new(Suit::Hearts, Rank::Ten)), "♥T".parse());
+        assert_eq!(Err(CardError::ParseRankError), "♥".parse::<Card>());
+        for card in (0..52u8).map(|c| Card::try_from(c).unwrap()) {
+            assert_eq!(Ok(card), card.to_string().parse());
+        }
+    }
+
     #[test]
     fn basic_card_methods() {
         let ace_of_spades: Card = Card::new(Suit::Spades, Rank::Ace);
@@ -0,0 +1,190 @@
+use crate::{card::{Card, Rank}, count::CountingSystem, deck::{DeckError, SINGLE_DECK_SIZE}};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const RANKS: usize = 13;
+
+// cards left in a shoe counted by rank, so probability math never has to scan the cards themselves
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Composition {
+    counts: [u16; RANKS],
+    total: u16,
+}
+
+impl Composition {
+    pub fn count(&self, rank: Rank) -> u16 {
+        self.counts[rank as usize]
+    }
+
+    pub fn total(&self) -> u16 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    // chance the next card dealt is `rank`; 0 for an empty shoe
+    pub fn prob_next(&self, rank: Rank) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+
+        self.count(rank) as f64 / self.total as f64
+    }
+
+    pub fn add(&mut self, rank: Rank) {
+        self.counts[rank as usize] += 1;
+        self.total += 1;
+    }
+
+    pub fn remove(&mut self, rank: Rank) -> Result<(), DeckError> {
+        let count: &mut u16 = &mut self.counts[rank as usize];
+
+        if *count == 0 {
+            return Err(DeckError::NotInShoe);
+        }
+        *count -= 1;
+        self.total -= 1;
+
+        Ok(())
+    }
+
+    // in standard 52 card decks, as counters estimate it
+    pub fn decks_remaining(&self) -> f64 {
+        self.total as f64 / SINGLE_DECK_SIZE as f64
+    }
+
+    // every rank cut down in proportion, as if `cards` were left from a neutral shoe
+    pub fn scaled_to(&self, cards: u16) -> Self {
+        if self.total == 0 {
+            return *self;
+        }
+
+        let cards: u16 = cards.min(self.total);
+        let exact: Vec<f64> = self.counts.iter().map(|&n| n as f64 * cards as f64 / self.total as f64).collect();
+        let mut scaled = Self::default();
+        for (r, share) in exact.iter().enumerate() {
+            scaled.counts[r] = share.floor() as u16;
+        }
+        scaled.total = scaled.counts.iter().sum();
+
+        // the rounding goes to the ranks that lost the most to it
+        let mut by_remainder: Vec<usize> = (0..RANKS).collect();
+        by_remainder.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+        for r in by_remainder.into_iter().take((cards - scaled.total) as usize) {
+            scaled.counts[r] += 1;
+            scaled.total += 1;
+        }
+
+        scaled
+    }
+
+    // takes out cards whose tags add up to `count`, the running count a counter would have seen, always from the
+    // best stocked rank that fits so the rest of the shoe keeps its shape
+    pub fn at_count<C>(self, system: &C, count: i64) -> Result<Self, DeckError>
+        where C: CountingSystem + ?Sized
+    {
+        let mut composition: Self = self;
+        let mut seen: i64 = 0;
+
+        while seen != count {
+            let need: i64 = count - seen;
+            let rank: Rank = composition.iter()
+                .filter(|&(rank, n)| {
+                    let tag: i64 = system.tag(rank) as i64;
+                    n > 0 && tag != 0 && tag.signum() == need.signum() && tag.abs() <= need.abs()
+                })
+                .max_by_key(|&(_, n)| n)
+                .map(|(rank, _)| rank)
+                .ok_or(DeckError::NotInShoe)?;
+
+            composition.remove(rank)?;
+            seen += system.tag(rank) as i64;
+        }
+
+        Ok(composition)
+    }
+
+    // what is in `self` but not in `part`; None if `part` holds anything `self` does not
+    pub fn without(&self, part: &Self) -> Option<Self> {
+        let mut rest = *self;
+
+        for (r, &n) in part.counts.iter().enumerate() {
+            rest.counts[r] = rest.counts[r].checked_sub(n)?;
+        }
+        rest.total -= part.total;
+
+        Some(rest)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Rank, u16)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(r, &count)| ((r as u8).try_into().unwrap(), count))
+    }
+}
+
+impl<'a> FromIterator<&'a Card> for Composition {
+    fn from_iter<I>(cards: I) -> Self
+        where I: IntoIterator<Item = &'a Card>
+    {
+        let mut composition = Self::default();
+
+        for card in cards {
+            composition.add(card.rank());
+        }
+
+        composition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::Rank, composition::Composition, count::{CountingSystem, HiLo, HiOptII}, deck::{Deck, DeckError}};
+
+    #[test]
+    fn counts_and_probabilities() {
+        let mut composition: Composition = Deck::new_shoe(6).composition();
+        assert_eq!(312, composition.total());
+        assert_eq!(24, composition.count(Rank::Ace));
+        assert_eq!(6.0, composition.decks_remaining());
+        assert_eq!(1.0 / 13.0, composition.prob_next(Rank::Five));
+
+        composition.remove(Rank::Five).unwrap();
+        assert_eq!(23.0 / 311.0, composition.prob_next(Rank::Five));
+        assert_eq!(311, composition.iter().map(|(_, n)| n as usize).sum::<usize>());
+
+        let mut empty = Composition::default();
+        assert_eq!(0.0, empty.prob_next(Rank::Ace));
+        assert_eq!(Err(DeckError::NotInShoe), empty.remove(Rank::Ace));
+    }
+
+    #[test]
+    fn targeted_shoes() {
+        let six_decks: Composition = Deck::new_shoe(6).composition();
+
+        // +6 by taking out six small cards
+        let plus_six = six_decks.at_count(&HiLo, 6).unwrap();
+        assert_eq!(306, plus_six.total());
+        assert_eq!(6, HiLo.count(&six_decks) - HiLo.count(&plus_six));
+        assert_eq!(six_decks.count(Rank::Ten), plus_six.count(Rank::Ten));
+
+        // a ten-poor shoe; tens are the only -2s, so an odd count is out of reach
+        let ten_poor = six_decks.at_count(&HiOptII, -6).unwrap();
+        assert_eq!(93, [Rank::Ten, Rank::Jack, Rank::Queen, Rank::King].into_iter().map(|r| ten_poor.count(r)).sum::<u16>());
+        assert_eq!(Err(DeckError::NotInShoe), six_decks.at_count(&HiOptII, -5));
+
+        // two decks left at a running count of +8
+        let two_decks = six_decks.scaled_to(104);
+        assert_eq!(8, two_decks.count(Rank::Ace));
+        let deep = two_decks.at_count(&HiLo, 8).unwrap();
+        assert_eq!(96, deep.total());
+
+        assert_eq!(Some(208), six_decks.without(&two_decks).map(|rest| rest.total()));
+        assert_eq!(None, two_decks.without(&six_decks));
+        assert_eq!(Err(DeckError::NotInShoe), Deck::new_shoe(1).composition().at_count(&HiLo, 21));
+    }
+}
@@ -0,0 +1,426 @@
+use crate::{card::{Card, Rank}, composition::Composition, deck::{Deck, SINGLE_DECK_SIZE}, rule::RuleSet};
+
+const RANKS: usize = 13;
+
+// a card counting system as a tag per rank
+pub trait CountingSystem {
+    fn name(&self) -> &str;
+
+    fn tag(&self, rank: Rank) -> i8;
+
+    // tags are this many times their real value, for systems with fractional tags
+    fn scale(&self) -> u8 {
+        1
+    }
+
+    // where the count starts off the top of a shoe; unbalanced systems use it to line up their key counts
+    fn initial_running_count(&self, _decks: usize) -> i64 {
+        0
+    }
+
+    // a full deck counts to zero
+    fn balanced(&self) -> bool {
+        self.count(&Deck::new_shoe(1).composition()) == 0
+    }
+
+    // aces are tagged 0 and tracked on the side for betting
+    fn ace_side_count(&self) -> bool {
+        false
+    }
+
+    fn count(&self, composition: &Composition) -> i64 {
+        composition.iter()
+            .map(|(rank, n)| self.tag(rank) as i64 * n as i64)
+            .sum()
+    }
+}
+
+// how finely decks remaining is judged by eye
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub enum DeckResolution {
+    #[default]
+    Exact,
+    HalfDeck,
+    FullDeck,
+}
+
+impl DeckResolution {
+    pub fn decks_remaining(&self, composition: &Composition) -> f64 {
+        self.judge(composition.decks_remaining())
+    }
+
+    // never rounds down to an empty shoe while cards are left
+    pub fn judge(&self, decks: f64) -> f64 {
+        let step: f64 = match self {
+            Self::Exact => return decks,
+            Self::HalfDeck => 0.5,
+            Self::FullDeck => 1.0,
+        };
+
+        if decks == 0.0 {
+            return 0.0;
+        }
+
+        ((decks / step).round() * step).max(step)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub enum TrueCountRounding {
+    #[default]
+    None,
+    Floor,
+    Truncate,
+    Nearest,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct TrueCount {
+    pub resolution: DeckResolution,
+    pub rounding: TrueCountRounding,
+}
+
+impl TrueCount {
+    pub fn new(resolution: DeckResolution, rounding: TrueCountRounding) -> Self {
+        Self { resolution, rounding }
+    }
+
+    // 0 once the shoe is empty
+    pub fn compute(&self, running_count: f64, remaining: &Composition) -> f64 {
+        self.compute_for_decks(running_count, remaining.decks_remaining())
+    }
+
+    // for when only the number of cards left is known, not which ones
+    pub fn compute_for_decks(&self, running_count: f64, decks_remaining: f64) -> f64 {
+        let decks: f64 = self.resolution.judge(decks_remaining);
+
+        if decks == 0.0 {
+            return 0.0;
+        }
+
+        let true_count: f64 = running_count / decks;
+
+        match self.rounding {
+            TrueCountRounding::None => true_count,
+            TrueCountRounding::Floor => true_count.floor(),
+            TrueCountRounding::Truncate => true_count.trunc(),
+            TrueCountRounding::Nearest => true_count.round(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct HiLo;
+
+impl CountingSystem for HiLo {
+    fn name(&self) -> &str {
+        "Hi-Lo"
+    }
+
+    fn tag(&self, rank: Rank) -> i8 {
+        match rank {
+            Rank::Two | Rank::Three | Rank::Four | Rank::Five | Rank::Six => 1,
+            Rank::Seven | Rank::Eight | Rank::Nine => 0,
+            Rank::Ace | Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => -1,
+        }
+    }
+}
+
+// knock-out; the seven counts, so a deck comes to +4
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct Ko;
+
+impl CountingSystem for Ko {
+    fn name(&self) -> &str {
+        "KO"
+    }
+
+    fn tag(&self, rank: Rank) -> i8 {
+        match rank {
+            Rank::Two | Rank::Three | Rank::Four | Rank::Five | Rank::Six | Rank::Seven => 1,
+            Rank::Eight | Rank::Nine => 0,
+            Rank::Ace | Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => -1,
+        }
+    }
+
+    fn initial_running_count(&self, decks: usize) -> i64 {
+        4 - 4 * decks as i64
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct HiOptII;
+
+impl CountingSystem for HiOptII {
+    fn name(&self) -> &str {
+        "Hi-Opt II"
+    }
+
+    fn tag(&self, rank: Rank) -> i8 {
+        match rank {
+            Rank::Four | Rank::Five => 2,
+            Rank::Two | Rank::Three | Rank::Six | Rank::Seven => 1,
+            Rank::Ace | Rank::Eight | Rank::Nine => 0,
+            Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => -2,
+        }
+    }
+
+    fn ace_side_count(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct OmegaII;
+
+impl CountingSystem for OmegaII {
+    fn name(&self) -> &str {
+        "Omega II"
+    }
+
+    fn tag(&self, rank: Rank) -> i8 {
+        match rank {
+            Rank::Four | Rank::Five | Rank::Six => 2,
+            Rank::Two | Rank::Three | Rank::Seven => 1,
+            Rank::Ace | Rank::Eight => 0,
+            Rank::Nine => -1,
+            Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => -2,
+        }
+    }
+
+    fn ace_side_count(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct Zen;
+
+impl CountingSystem for Zen {
+    fn name(&self) -> &str {
+        "Zen"
+    }
+
+    fn tag(&self, rank: Rank) -> i8 {
+        match rank {
+            Rank::Four | Rank::Five | Rank::Six => 2,
+            Rank::Two | Rank::Three | Rank::Seven => 1,
+            Rank::Eight | Rank::Nine => 0,
+            Rank::Ace => -1,
+            Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => -2,
+        }
+    }
+}
+
+// any tag table, indexed ace through king; `irc` is the initial running count for the shoe it will be used on
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct CustomCount {
+    tags: [i8; RANKS],
+    scale: u8,
+    balanced: bool,
+    irc: i64,
+}
+
+impl CustomCount {
+    pub fn new(tags: [i8; RANKS], balanced: bool, irc: i64) -> Self {
+        Self { tags, scale: 1, balanced, irc }
+    }
+
+    // tags of 1 and 2 with a scale of 2 count a half and a one
+    pub fn scaled(self, scale: u8) -> Self {
+        Self { scale: scale.max(1), ..self }
+    }
+}
+
+impl dyn CountingSystem {
+    // called as `<dyn CountingSystem>::custom(..)`, for tag tables other than the built in systems
+    pub fn custom(tags: [i8; RANKS], balanced: bool, irc: i64) -> CustomCount {
+        CustomCount::new(tags, balanced, irc)
+    }
+}
+
+impl CountingSystem for CustomCount {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn tag(&self, rank: Rank) -> i8 {
+        self.tags[rank as usize]
+    }
+
+    fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    fn initial_running_count(&self, _decks: usize) -> i64 {
+        self.irc
+    }
+
+    fn balanced(&self) -> bool {
+        self.balanced
+    }
+}
+
+// counts only what it is shown, so the hole card stays out until it is turned over
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CardCounter<C> {
+    system: C,
+    seen: Composition,
+    running_count: i64,
+    aces: u16,
+    decks: usize,
+    shoe_size: usize,
+}
+
+impl<C> CardCounter<C>
+    where C: CountingSystem
+{
+    pub fn new(system: C, rules: &RuleSet) -> Self {
+        Self {
+            running_count: system.initial_running_count(rules.decks()),
+            system,
+            seen: Composition::default(),
+            aces: 0,
+            decks: rules.decks(),
+            shoe_size: Deck::for_rules(rules).cards_left(),
+        }
+    }
+
+    pub fn system(&self) -> &C {
+        &self.system
+    }
+
+    pub fn see(&mut self, card: Card) {
+        self.seen.add(card.rank());
+        self.running_count += self.system.tag(card.rank()) as i64;
+
+        if card.rank() == Rank::Ace {
+            self.aces += 1;
+        }
+    }
+
+    pub fn see_all<I>(&mut self, cards: I)
+        where I: IntoIterator<Item = Card>
+    {
+        for card in cards {
+            self.see(card);
+        }
+    }
+
+    // back to the top of a fresh shoe
+    pub fn shuffled(&mut self) {
+        self.seen = Composition::default();
+        self.running_count = self.system.initial_running_count(self.decks);
+        self.aces = 0;
+    }
+
+    pub fn seen(&self) -> &Composition {
+        &self.seen
+    }
+
+    // in the system's scaled units
+    pub fn running_count(&self) -> i64 {
+        self.running_count
+    }
+
+    // for systems that keep the aces on the side
+    pub fn aces_seen(&self) -> u16 {
+        self.aces
+    }
+
+    // unseen cards, the hole card included, are all treated as still in the shoe
+    pub fn decks_remaining(&self) -> f64 {
+        (self.shoe_size as f64 - self.seen.total() as f64).max(0.0) / SINGLE_DECK_SIZE as f64
+    }
+
+    pub fn true_count(&self, method: TrueCount) -> f64 {
+        method.compute_for_decks(self.running_count as f64 / self.system.scale() as f64, self.decks_remaining())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, count::{CardCounter, CountingSystem, CustomCount, DeckResolution, HiLo, HiOptII, Ko, OmegaII, TrueCount, TrueCountRounding, Zen}, deck::Deck, game::deal_initial, rule::RuleSet};
+    use rand::thread_rng;
+
+    #[test]
+    fn built_in_systems() {
+        let systems: Vec<Box<dyn CountingSystem>> = vec![Box::new(HiLo), Box::new(HiOptII), Box::new(OmegaII), Box::new(Zen)];
+        for system in &systems {
+            assert!(system.balanced(), "{}", system.name());
+        }
+
+        assert!(!Ko.balanced());
+        assert_eq!(24, Ko.count(&Deck::new_shoe(6).composition()));
+        assert!(OmegaII.ace_side_count() && !Zen.ace_side_count());
+        assert_eq!(-20, Ko.initial_running_count(6));
+    }
+
+    #[test]
+    fn seen_cards_only() {
+        let rules = RuleSet::builder().decks(1).players(1).build().unwrap();
+        let mut deck = Deck::from_cards(
+            [Rank::Five, Rank::Ten, Rank::Six, Rank::King, Rank::Ace, Rank::Two]
+                .into_iter()
+                .map(|rank| Card::new(Suit::Clubs, rank))
+                .collect()
+        );
+        let deal = deal_initial(&mut deck, &rules, &mut thread_rng()).unwrap();
+
+        // player 5,6; dealer shows the ten and the king stays face down
+        let mut counter = CardCounter::new(HiLo, &rules);
+        counter.see_all(deal.visible_cards(&rules));
+        assert_eq!(1, counter.running_count());
+        assert_eq!(3, counter.seen().total());
+        assert_eq!(49.0 / 52.0, counter.decks_remaining());
+        assert_eq!(52.0 / 49.0, counter.true_count(TrueCount::default()));
+
+        counter.see(deal.dealer().cards()[1]);
+        assert_eq!(0, counter.running_count());
+
+        let mut ko = CardCounter::new(Ko, &RuleSet::builder().build().unwrap());
+        ko.see(Card::new(Suit::Hearts, Rank::Ace));
+        assert_eq!((-21, 1), (ko.running_count(), ko.aces_seen()));
+        ko.shuffled();
+        assert_eq!((-20, 0), (ko.running_count(), ko.aces_seen()));
+    }
+
+    #[test]
+    fn custom_systems() {
+        // wong halves, doubled: 2 and 7 a half, 5 one and a half, 9 minus a half
+        let halves = CustomCount::new([-2, 1, 2, 2, 3, 2, 1, 0, -1, -2, -2, -2, -2], true, 0).scaled(2);
+        assert_eq!(2, halves.scale());
+        let mut deck = Deck::new_shoe(1).composition();
+        assert_eq!(0, halves.count(&deck));
+        deck.remove(Rank::Five).unwrap();
+        assert_eq!(-3, halves.count(&deck));
+        assert!(halves.balanced());
+
+        let hi_lo = <dyn CountingSystem>::custom([-1, 1, 1, 1, 1, 1, 0, 0, 0, -1, -1, -1, -1], true, 0);
+        let shoe = Deck::new_shoe(2).composition();
+        assert_eq!(HiLo.count(&shoe), hi_lo.count(&shoe));
+    }
+
+    #[test]
+    fn true_counts() {
+        // 2.6 decks left
+        let mut remaining = Deck::new_shoe(3).composition();
+        for (rank, keep) in [(Rank::Two, 1), (Rank::Three, 2)] {
+            while remaining.count(rank) > keep {
+                remaining.remove(rank).unwrap();
+            }
+        }
+        assert_eq!(135, remaining.total());
+
+        let exact = TrueCount::default().compute(-7.0, &remaining);
+        assert!((exact + 7.0 / (135.0 / 52.0)).abs() < 1e-9);
+
+        let half_decks = TrueCount::new(DeckResolution::HalfDeck, TrueCountRounding::None);
+        assert_eq!(-2.8, half_decks.compute(-7.0, &remaining));
+        assert_eq!(-3.0, TrueCount { rounding: TrueCountRounding::Floor, ..half_decks }.compute(-7.0, &remaining));
+        assert_eq!(-2.0, TrueCount { rounding: TrueCountRounding::Truncate, ..half_decks }.compute(-7.0, &remaining));
+        assert_eq!(-2.0, TrueCount::new(DeckResolution::FullDeck, TrueCountRounding::Nearest).compute(-7.0, &remaining));
+
+        assert_eq!(0.0, TrueCount::default().compute(5.0, &Default::default()));
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{deck::{Deck, DeckError}, hand::{Bust, Fresh, Hand, HandValue, Hit, Stand}, rule::{DealerOnSoft17, RuleSet}};
+use rand::Rng;
+
+pub trait DealerStrategy {
+    fn hits(&mut self, value: HandValue, rules: &RuleSet) -> bool;
+}
+
+// follows whatever the rule set says about soft 17
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct Standard;
+
+impl DealerStrategy for Standard {
+    fn hits(&mut self, value: HandValue, rules: &RuleSet) -> bool {
+        rules.dealer_on_soft_17().hits(value, rules)
+    }
+}
+
+impl DealerStrategy for DealerOnSoft17 {
+    fn hits(&mut self, value: HandValue, _rules: &RuleSet) -> bool {
+        match (value, self) {
+            (HandValue::Soft { upper, .. }, DealerOnSoft17::H17) if upper <= 21 => upper <= 17,
+            (HandValue::Soft { upper, .. }, DealerOnSoft17::S17) if upper <= 21 => upper < 17,
+            (HandValue::Soft { lower, .. }, _) => lower < 17,
+            (HandValue::Hard(v), _) => v < 17,
+        }
+    }
+}
+
+// plays out a fixed list of decisions, then stands; for training scenarios
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Default)]
+pub struct Scripted {
+    decisions: Vec<bool>,
+}
+
+impl Scripted {
+    pub fn new(decisions: Vec<bool>) -> Self {
+        Self { decisions: decisions.into_iter().rev().collect() }
+    }
+}
+
+impl DealerStrategy for Scripted {
+    fn hits(&mut self, _value: HandValue, _rules: &RuleSet) -> bool {
+        self.decisions.pop().unwrap_or(false)
+    }
+}
+
+impl<F> DealerStrategy for F
+    where F: FnMut(HandValue, &RuleSet) -> bool
+{
+    fn hits(&mut self, value: HandValue, rules: &RuleSet) -> bool {
+        self(value, rules)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub enum DealerHand {
+    Stand(Hand<Stand>),
+    Bust(Hand<Bust>),
+}
+
+pub fn play<D, R>(hand: Hand<Fresh>, deck: &mut Deck, strategy: &mut D, rules: &RuleSet, rng: &mut R) -> Result<DealerHand, DeckError>
+    where D: DealerStrategy, R: Rng + ?Sized
+{
+    let mut hand = hand;
+
+    while strategy.hits(hand.value(), rules) {
+        hand = match hand.hit(deck.deal_with(rules.shoe_exhaustion(), rng)?) {
+            Hit::Live(hand) => hand,
+            Hit::Bust(hand) => return Ok(DealerHand::Bust(hand)),
+        };
+    }
+
+    Ok(DealerHand::Stand(hand.stand()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, dealer::{play, DealerHand, DealerStrategy, Scripted, Standard}, deck::Deck, hand::{Hand, HandValue}, rule::{DealerOnSoft17, DoubleRule, RuleSet, SurrenderRule}};
+    use rand::thread_rng;
+
+    fn rules(dealer_on_soft_17: DealerOnSoft17) -> RuleSet {
+        RuleSet::builder()
+            .dealer_on_soft_17(dealer_on_soft_17)
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .surrender(SurrenderRule::Late)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn soft_17_rules() {
+        let soft_17 = HandValue::Soft { lower: 7, upper: 17 };
+
+        assert!(Standard.hits(soft_17, &rules(DealerOnSoft17::H17)));
+        assert!(!Standard.hits(soft_17, &rules(DealerOnSoft17::S17)));
+        assert!(Standard.hits(HandValue::Soft { lower: 16, upper: 26 }, &rules(DealerOnSoft17::S17)));
+        assert!(!Standard.hits(HandValue::Hard(17), &rules(DealerOnSoft17::H17)));
+
+        // stands on every 17 but hits soft 18
+        let mut pontoon = |value: HandValue, _: &RuleSet| match value {
+            HandValue::Soft { upper: 18, .. } => true,
+            HandValue::Soft { lower, upper } => if upper <= 21 { upper < 17 } else { lower < 17 },
+            HandValue::Hard(v) => v < 17,
+        };
+        assert!(pontoon.hits(HandValue::Soft { lower: 8, upper: 18 }, &rules(DealerOnSoft17::S17)));
+    }
+
+    #[test]
+    fn scripted_dealer() {
+        let mut deck = Deck::new_shoe(1);
+        let hand = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Eight),
+            ]
+        );
+
+        // the unshuffled shoe deals the king of spades first
+        match play(hand, &mut deck, &mut Scripted::new(vec![true]), &rules(DealerOnSoft17::S17), &mut thread_rng()).unwrap() {
+            DealerHand::Bust(hand) => assert_eq!(3, hand.cards().len()),
+            DealerHand::Stand(_) => panic!("28 is bust"),
+        }
+    }
+}
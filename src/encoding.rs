@@ -0,0 +1,79 @@
+use crate::{card::Rank, hand::{Hand, HandInfo, HandState, HandValue}, play::Context};
+
+// feature layouts are never changed once published; new layouts get a new version
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum Encoding {
+    // total / 21, soft, pair, upcard one-hot (A, 2-9, T), true count, penetration
+    V1,
+}
+
+impl Encoding {
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::V1 => 1,
+        }
+    }
+
+    pub fn features(&self) -> usize {
+        match self {
+            Self::V1 => 15,
+        }
+    }
+
+    // `penetration` is the fraction of the shoe already dealt
+    pub fn encode<S>(&self, hand: &Hand<S>, context: &Context, true_count: f64, penetration: f64) -> Vec<f32>
+        where S: HandState
+    {
+        match self {
+            Self::V1 => {
+                let (total, soft) = match hand.effective_value() {
+                    HandValue::Hard(v) => (v, false),
+                    HandValue::Soft { upper, .. } => (upper, true),
+                };
+
+                let mut features: Vec<f32> = Vec::with_capacity(self.features());
+                features.push(total as f32 / 21.0);
+                features.push(flag(soft));
+                features.push(flag(hand.is_pair()));
+
+                let upcard: usize = match context.upcard().rank() {
+                    Rank::Jack | Rank::Queen | Rank::King => Rank::Ten as usize,
+                    rank => rank as usize,
+                };
+                features.extend((0..10).map(|i| flag(i == upcard)));
+
+                features.push(true_count as f32);
+                features.push(penetration as f32);
+
+                features
+            },
+        }
+    }
+}
+
+fn flag(b: bool) -> f32 {
+    if b { 1.0 } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, encoding::Encoding, hand::Hand, play::Context};
+
+    #[test]
+    fn encode_v1() {
+        let hand = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Hearts, Rank::Ace),
+            ]
+        );
+        let context = Context::new(Card::new(Suit::Spades, Rank::Queen), 1);
+
+        let features = Encoding::V1.encode(&hand, &context, 2.5, 0.25);
+        assert_eq!(Encoding::V1.features(), features.len());
+        assert_eq!(
+            vec![12.0 / 21.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.5, 0.25],
+            features,
+        );
+    }
+}
@@ -0,0 +1,909 @@
+use std::{fmt, str::FromStr};
+use crate::{card::Card, deck::{Deck, DeckError}, hand::{Fresh, Hand, HandInfo, HandId, HandOrigin, HandState, HandStateKind, HandValue}, paytable::Paytable, play::CodeError, rule::{EnhcLoss, RuleSet, SettlementPolicy, ShuffleKind, TieRule}};
+#[cfg(feature = "variant-spanish21")]
+use crate::{card::{Rank, Suit}, rule::{Bonus, BonusPayouts}};
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// average cards drawn per hand, dealer included
+const CARDS_PER_HAND: f64 = 2.7;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Outcome {
+    Blackjack,
+    Win,
+    Push,
+    Lose,
+    Surrender,
+}
+
+impl Outcome {
+    // net result per unit wagered on the hand
+    pub fn payout(&self, rules: &RuleSet) -> f64 {
+        match self {
+            Self::Blackjack => rules.blackjack_payout().ratio(),
+            Self::Win => 1.0,
+            Self::Push => 0.0,
+            Self::Lose => -1.0,
+            Self::Surrender => -0.5,
+        }
+    }
+
+    pub fn paytable(rules: &RuleSet) -> Paytable<Outcome> {
+        let outcomes = [Self::Blackjack, Self::Win, Self::Push, Self::Lose, Self::Surrender];
+
+        Paytable::new(outcomes.map(|o| (o, o.payout(rules)))).expect("rule set payouts are validated")
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Blackjack => "BJ",
+            Self::Win => "W",
+            Self::Push => "P",
+            Self::Lose => "L",
+            Self::Surrender => "R",
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "BJ" => Ok(Self::Blackjack),
+            "W" => Ok(Self::Win),
+            "P" => Ok(Self::Push),
+            "L" => Ok(Self::Lose),
+            "R" => Ok(Self::Surrender),
+            _ => Err(CodeError::UnknownOutcome),
+        }
+    }
+}
+
+pub fn settle<S, D>(hand: &Hand<S>, dealer: &Hand<D>, rules: &RuleSet) -> Outcome
+    where S: HandState, D: HandState
+{
+    if hand.state() == HandStateKind::Surrender {
+        // late surrender is never offered against a dealer natural, so the whole bet is lost
+        if dealer.is_natural() && !rules.surrender().is_early() {
+            return Outcome::Lose;
+        }
+
+        return Outcome::Surrender;
+    }
+
+    if hand.is_bust() {
+        return Outcome::Lose;
+    }
+
+    let policy: SettlementPolicy = rules.settlement();
+
+    match (hand.is_natural(), dealer.is_natural()) {
+        #[cfg(feature = "variant-spanish21")]
+        (true, true) if rules.spanish_21() => return Outcome::Blackjack,
+        (true, true) if rules.tie_rule() == TieRule::DealerWins => return Outcome::Lose,
+        (true, true) => return Outcome::Push,
+        (true, false) => return Outcome::Blackjack,
+        (false, true) => {
+            let split_21: bool = hand.origin() == HandOrigin::Split && hand.cards().len() == 2 && hand.is_21();
+
+            if split_21 && policy.split_21_pushes_natural() {
+                return Outcome::Push;
+            }
+
+            return Outcome::Lose;
+        },
+        (false, false) => {},
+    }
+
+    if hand.is_charlie(rules) || (policy.player_21_wins() && hand.is_21()) {
+        return Outcome::Win;
+    }
+
+    let cards: usize = hand.cards().len();
+    let dealer: HandValue = dealer.value();
+
+    if dealer.is_bust() {
+        if rules.push_22() && dealer.best() == 22 {
+            return Outcome::Push;
+        }
+
+        return Outcome::Win;
+    }
+
+    match hand.value().cmp_blackjack(&dealer) {
+        std::cmp::Ordering::Greater => Outcome::Win,
+        std::cmp::Ordering::Equal if policy.five_card_wins_ties() && cards >= 5 => Outcome::Win,
+        std::cmp::Ordering::Equal if rules.tie_rule() == TieRule::Push => Outcome::Push,
+        std::cmp::Ordering::Equal => Outcome::Lose,
+        std::cmp::Ordering::Less => Outcome::Lose,
+    }
+}
+
+// every bonus the hand qualifies for; three-card bonuses never apply to doubled hands
+#[cfg(feature = "variant-spanish21")]
+pub fn bonuses<S>(hand: &Hand<S>) -> Vec<Bonus>
+    where S: HandState
+{
+    let cards: &[Card] = hand.cards();
+    let suited: bool = cards.iter().all(|c| c.suit() == cards[0].suit());
+    let spades: bool = cards.iter().all(|c| c.suit() == Suit::Spades);
+
+    if hand.is_natural() {
+        return if suited { vec![Bonus::SuitedBlackjack] } else { Vec::new() };
+    }
+
+    if cards.len() != 3 || hand.state() == HandStateKind::DoubleDown {
+        return Vec::new();
+    }
+
+    let mut ranks: Vec<Rank> = cards.iter().map(|c| c.rank()).collect();
+    ranks.sort();
+
+    let (mixed, same_suit, all_spades) = if ranks == [Rank::Six, Rank::Seven, Rank::Eight] {
+        (Bonus::SixSevenEight, Bonus::SixSevenEightSuited, Bonus::SixSevenEightSpades)
+    } else if ranks == [Rank::Seven; 3] {
+        (Bonus::SevenSevenSeven, Bonus::SevenSevenSevenSuited, Bonus::SevenSevenSevenSpades)
+    } else {
+        return Vec::new();
+    };
+
+    let mut bonuses: Vec<Bonus> = vec![mixed];
+    if suited {
+        bonuses.push(same_suit);
+    }
+    if spades {
+        bonuses.push(all_spades);
+    }
+
+    bonuses
+}
+
+// best multiplier the rule set's bonus table pays for this hand
+#[cfg(feature = "variant-spanish21")]
+pub fn bonus_multiplier<S>(hand: &Hand<S>, rules: &RuleSet) -> Option<f64>
+    where S: HandState
+{
+    let table: &BonusPayouts = rules.bonuses()?;
+
+    bonuses(hand)
+        .iter()
+        .filter_map(|b| table.multiplier(b))
+        .max_by(|a, b| a.total_cmp(b))
+}
+
+// units won or lost on a hand started with one unit, counting doubles; free bet units only ever pay out
+pub fn net_units<S>(hand: &Hand<S>, outcome: Outcome, rules: &RuleSet) -> f64
+    where S: HandState
+{
+    let units: f64 = if hand.state() == HandStateKind::DoubleDown { 2.0 } else { 1.0 };
+    let own: f64 = units - hand.free_units() as f64;
+
+    match outcome {
+        Outcome::Lose | Outcome::Surrender => own * outcome.payout(rules),
+        #[cfg(feature = "variant-spanish21")]
+        Outcome::Win | Outcome::Blackjack => units * bonus_multiplier(hand, rules).unwrap_or(outcome.payout(rules)),
+        _ => units * outcome.payout(rules),
+    }
+}
+
+// net units for one hand once the dealer has finished, with the no hole card loss rules applied
+pub fn settle_units<S, D>(hand: &Hand<S>, dealer: &Hand<D>, rules: &RuleSet) -> f64
+    where S: HandState, D: HandState
+{
+    settle_hand(hand, dealer, rules).units()
+}
+
+// the full settlement of one hand, named by its id so results can be matched back after splits
+pub fn settle_hand<S, D>(hand: &Hand<S>, dealer: &Hand<D>, rules: &RuleSet) -> Settlement
+    where S: HandState, D: HandState
+{
+    let outcome: Outcome = settle(hand, dealer, rules);
+    let units: f64 = net_units(hand, outcome, rules);
+
+    let units: f64 = match rules.no_hole_card() {
+        Some(loss) if outcome == Outcome::Lose && dealer.is_natural() => units.max(-HandStake::of(hand).natural_loss(loss)),
+        _ => units,
+    };
+
+    Settlement { id: hand.id(), outcome, units }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct Settlement {
+    id: HandId,
+    outcome: Outcome,
+    units: f64,
+}
+
+impl Settlement {
+    pub fn id(&self) -> HandId {
+        self.id
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    pub fn units(&self) -> f64 {
+        self.units
+    }
+}
+
+// the money a hand put on the table, enough to settle it against a dealer natural
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct HandStake {
+    id: HandId,
+    units: f64,
+    bust: bool,
+}
+
+impl HandStake {
+    pub fn of<S>(hand: &Hand<S>) -> Self
+        where S: HandState
+    {
+        let units: f64 = if hand.state() == HandStateKind::DoubleDown { 2.0 } else { 1.0 };
+
+        Self { id: hand.id(), units, bust: hand.is_bust() }
+    }
+
+    pub fn id(&self) -> HandId {
+        self.id
+    }
+
+    pub fn units(&self) -> f64 {
+        self.units
+    }
+
+    pub fn bust(&self) -> bool {
+        self.bust
+    }
+
+    // the original bet rides on the hand that kept it through every split; busted bets go too under BB+1
+    fn natural_loss(&self, loss: EnhcLoss) -> f64 {
+        match loss {
+            EnhcLoss::AllBets => self.units,
+            EnhcLoss::BustedBetsPlusOne if self.bust => self.units,
+            _ if self.id.keeps_original_bet() => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+// units lost to a dealer natural by every hand grown from one original bet
+pub fn dealer_natural_loss(stakes: &[HandStake], rules: &RuleSet) -> f64 {
+    match rules.no_hole_card() {
+        // with a peek the round ends before anything beyond the original bet is wagered
+        None => stakes.iter().map(|s| s.units).sum(),
+        Some(loss) => stakes.iter().map(|s| s.natural_loss(loss)).sum(),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub struct InitialDeal {
+    seats: Vec<Hand<Fresh>>,
+    dealer: Hand<Fresh>,
+}
+
+impl InitialDeal {
+    pub fn seats(&self) -> &[Hand<Fresh>] {
+        &self.seats
+    }
+
+    pub fn dealer(&self) -> &Hand<Fresh> {
+        &self.dealer
+    }
+
+    // the dealer's first card goes face up
+    pub fn upcard(&self) -> Card {
+        self.dealer.cards()[0]
+    }
+
+    // what a player at the table gets to see; the hole card only in double exposure games
+    pub fn visible_cards(&self, rules: &RuleSet) -> Vec<Card> {
+        let mut cards: Vec<Card> = self.seats.iter().flat_map(|seat| seat.cards().iter().copied()).collect();
+
+        match rules.double_exposure() {
+            true => cards.extend(self.dealer.cards()),
+            false => cards.push(self.upcard()),
+        }
+
+        cards
+    }
+
+    pub fn into_hands(self) -> (Vec<Hand<Fresh>>, Hand<Fresh>) {
+        (self.seats, self.dealer)
+    }
+}
+
+// one card to every seat then the dealer, twice round, drawn from the shoe up front
+pub fn deal_initial<R>(deck: &mut Deck, rules: &RuleSet, rng: &mut R) -> Result<InitialDeal, DeckError>
+    where R: Rng + ?Sized
+{
+    let hands: usize = rules.players() + 1;
+    let cards: Vec<Card> = deck.deal_n(2 * hands, rules.shoe_exhaustion(), rng)?;
+
+    let mut stacks: Vec<Hand<Fresh>> = (0..hands)
+        .map(|seat| Hand::new([cards[seat], cards[seat + hands]]).with_id(HandId::seat(seat as u8)))
+        .collect();
+    let dealer: Hand<Fresh> = stacks.pop().expect("the dealer always has a hand").with_id(HandId::DEALER);
+
+    Ok(InitialDeal { seats: stacks, dealer })
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct TableTiming {
+    seconds_per_round: f64,
+    seconds_per_player: f64,
+    shuffle_seconds: f64,
+}
+
+impl TableTiming {
+    pub fn new(seconds_per_round: f64, seconds_per_player: f64, shuffle_seconds: f64) -> Self {
+        Self { seconds_per_round, seconds_per_player, shuffle_seconds }
+    }
+
+    pub fn hand_shuffled() -> Self {
+        Self::new(12.0, 10.0, 150.0)
+    }
+
+    // a continuous shuffler never stops the game
+    pub fn csm() -> Self {
+        Self::new(12.0, 10.0, 0.0)
+    }
+
+    // two shoes in rotation: the next one is shuffled off to the side, so only the swap stops the game
+    pub fn rotating_shoes() -> Self {
+        Self::new(12.0, 10.0, 20.0)
+    }
+
+    pub fn seconds_per_round(&self) -> f64 {
+        self.seconds_per_round
+    }
+
+    pub fn seconds_per_player(&self) -> f64 {
+        self.seconds_per_player
+    }
+
+    pub fn shuffle_seconds(&self) -> f64 {
+        self.shuffle_seconds
+    }
+
+    pub fn round_seconds(&self, players: usize) -> f64 {
+        self.seconds_per_round + self.seconds_per_player * players as f64
+    }
+
+    pub fn rounds_per_hour(&self, rules: &RuleSet) -> f64 {
+        let round: f64 = self.round_seconds(rules.players());
+
+        let rounds_per_shoe: f64 = match rules.shuffle_kind() {
+            ShuffleKind::Continuous => return 3600.0 / round,
+            ShuffleKind::Threshold(left) => {
+                let dealt: f64 = (rules.decks() * rules.deck_size()) as f64 - left as f64;
+                (dealt / (CARDS_PER_HAND * (rules.players() + 1) as f64)).max(1.0)
+            },
+        };
+
+        3600.0 * rounds_per_shoe / (rounds_per_shoe * round + self.shuffle_seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, deck::Deck, game::{deal_initial, dealer_natural_loss, net_units, settle, settle_hand, settle_units, HandStake, Outcome, TableTiming}, hand::{Hand, HandId, Hit, SplitHands}, rule::{BlackjackPayout, DoubleRule, EnhcLoss, RuleSet, SettlementPolicy, ShuffleKind, SurrenderRule, TieRule}};
+    #[cfg(feature = "variant-spanish21")]
+    use crate::{game::bonus_multiplier, paytable::Paytable, rule::Bonus};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn settle_hands() {
+        let rules = |charlie: Option<u8>| RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .surrender(SurrenderRule::Late)
+            .charlie(charlie)
+            .build()
+            .unwrap();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Nine),
+            ]
+        ).stand();
+
+        let natural = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Clubs, Rank::King),
+            ]
+        );
+        assert_eq!(Outcome::Blackjack, settle(&natural, &dealer, &rules(None)));
+        assert_eq!(1.5, Outcome::Blackjack.payout(&rules(None)));
+
+        assert_eq!("BJ", Outcome::Blackjack.to_string());
+        assert_eq!(Ok(Outcome::Push), "P".parse());
+
+        let six_to_five = RuleSet::builder().blackjack_payout(BlackjackPayout::SixToFive).build().unwrap();
+        assert_eq!(1.2, Outcome::Blackjack.payout(&six_to_five));
+
+        let mut small = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Two),
+                Card::new(Suit::Clubs, Rank::Three),
+            ]
+        );
+        for rank in [Rank::Two, Rank::Three, Rank::Four] {
+            small = match small.hit(Card::new(Suit::Hearts, rank)) {
+                Hit::Live(hand) => hand,
+                Hit::Bust(_) => panic!("14 is not bust"),
+            };
+        }
+
+        assert_eq!(Outcome::Lose, settle(&small, &dealer, &rules(None)));
+        assert_eq!(Outcome::Win, settle(&small, &dealer, &rules(Some(5))));
+
+        let sixteen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        );
+        assert_eq!(Outcome::Surrender, settle(&sixteen.surrender(), &dealer, &rules(None)));
+    }
+
+    #[test]
+    fn split_21_is_not_blackjack() {
+        let rules = RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .surrender(SurrenderRule::Late)
+            .build()
+            .unwrap();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Nine),
+            ]
+        ).stand();
+
+        let split = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::King),
+                Card::new(Suit::Hearts, Rank::King),
+            ]
+        ).split(Card::new(Suit::Clubs, Rank::Ace), Card::new(Suit::Hearts, Rank::Two));
+
+        let (left, _) = match split {
+            SplitHands::Hands(left, right) => (left, right),
+            SplitHands::Aces(..) => panic!("kings are not aces"),
+        };
+
+        assert_eq!(Outcome::Win, settle(&left.stand(), &dealer, &rules));
+    }
+
+    #[test]
+    fn dealer_22_pushes() {
+        let rules = |push_22: bool| RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .surrender(SurrenderRule::Late)
+            .push_22(push_22)
+            .build()
+            .unwrap();
+
+        let dealer = |rank: Rank| match Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Six),
+            ]
+        ).hit(Card::new(Suit::Hearts, rank)) {
+            Hit::Bust(hand) => hand,
+            Hit::Live(_) => panic!("dealer should bust"),
+        };
+
+        let eighteen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Eight),
+            ]
+        ).stand();
+
+        let natural = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Clubs, Rank::King),
+            ]
+        );
+
+        assert_eq!(Outcome::Win, settle(&eighteen, &dealer(Rank::Six), &rules(false)));
+        assert_eq!(Outcome::Push, settle(&eighteen, &dealer(Rank::Six), &rules(true)));
+        assert_eq!(Outcome::Win, settle(&eighteen, &dealer(Rank::Seven), &rules(true)));
+        assert_eq!(Outcome::Blackjack, settle(&natural, &dealer(Rank::Six), &rules(true)));
+    }
+
+    #[test]
+    fn rounds_per_hour() {
+        let heads_up = RuleSet::builder().build().unwrap();
+        let full = RuleSet::builder().players(7).build().unwrap();
+
+        let hand_shuffled = TableTiming::hand_shuffled();
+        assert!(hand_shuffled.rounds_per_hour(&heads_up) > hand_shuffled.rounds_per_hour(&full));
+        assert!(TableTiming::csm().rounds_per_hour(&full) > hand_shuffled.rounds_per_hour(&full));
+
+        let continuous = RuleSet::builder().shuffle_kind(ShuffleKind::Continuous).build().unwrap();
+        assert_eq!(3600.0 / 22.0, TableTiming::csm().rounds_per_hour(&continuous));
+    }
+
+    #[test]
+    fn early_surrender_beats_dealer_natural() {
+        let rules = |surrender: SurrenderRule| RuleSet::builder()
+            .surrender(surrender)
+            .build()
+            .unwrap();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ace),
+                Card::new(Suit::Spades, Rank::King),
+            ]
+        );
+
+        let sixteen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        ).surrender();
+
+        assert_eq!(Outcome::Surrender, settle(&sixteen, &dealer, &rules(SurrenderRule::Early)));
+        assert_eq!(Outcome::Lose, settle(&sixteen, &dealer, &rules(SurrenderRule::Late)));
+    }
+
+    #[test]
+    fn double_exposure_ties_lose() {
+        let rules = RuleSet::double_exposure_game();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Nine),
+            ]
+        ).stand();
+
+        let nineteen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Nine),
+            ]
+        ).stand();
+        assert_eq!(Outcome::Lose, settle(&nineteen, &dealer, &rules));
+        assert_eq!(Outcome::Push, settle(&nineteen, &dealer, &RuleSet::builder().build().unwrap()));
+
+        let natural = |suit: Suit| Hand::new(
+            vec![
+                Card::new(suit, Rank::Ace),
+                Card::new(suit, Rank::King),
+            ]
+        );
+        assert_eq!(Outcome::Push, settle(&natural(Suit::Clubs), &natural(Suit::Spades), &rules));
+        assert_eq!(Outcome::Blackjack, settle(&natural(Suit::Clubs), &dealer, &rules));
+        assert_eq!(1.0, Outcome::Blackjack.payout(&rules));
+    }
+
+    #[test]
+    fn tie_rules() {
+        let rules = |tie_rule: TieRule| RuleSet::builder().tie_rule(tie_rule).build().unwrap();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Eight),
+            ]
+        ).stand();
+        let eighteen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Nine),
+                Card::new(Suit::Clubs, Rank::Nine),
+            ]
+        ).stand();
+        assert_eq!(Outcome::Push, settle(&eighteen, &dealer, &rules(TieRule::Push)));
+        assert_eq!(Outcome::Lose, settle(&eighteen, &dealer, &rules(TieRule::DealerWins)));
+        assert_eq!(Outcome::Lose, settle(&eighteen, &dealer, &rules(TieRule::DealerWinsExceptNaturals)));
+
+        let natural = |suit: Suit| Hand::new(
+            vec![
+                Card::new(suit, Rank::Ace),
+                Card::new(suit, Rank::Queen),
+            ]
+        );
+        assert_eq!(Outcome::Push, settle(&natural(Suit::Clubs), &natural(Suit::Spades), &rules(TieRule::Push)));
+        assert_eq!(Outcome::Lose, settle(&natural(Suit::Clubs), &natural(Suit::Spades), &rules(TieRule::DealerWins)));
+        assert_eq!(
+            Outcome::Push,
+            settle(&natural(Suit::Clubs), &natural(Suit::Spades), &rules(TieRule::DealerWinsExceptNaturals)),
+        );
+    }
+
+    #[cfg(feature = "variant-switch")]
+    #[test]
+    fn switched_21_is_not_blackjack() {
+        let rules = RuleSet::blackjack_switch();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Nine),
+            ]
+        ).stand();
+
+        let first = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        );
+        let second = Hand::new(
+            vec![
+                Card::new(Suit::Hearts, Rank::Five),
+                Card::new(Suit::Hearts, Rank::King),
+            ]
+        );
+
+        let rejected = first.try_switch(second, &RuleSet::builder().build().unwrap()).unwrap_err();
+        assert_eq!(crate::hand::HandError::SwitchNotAllowed, rejected.error());
+        let (first, second) = rejected.into_hands();
+
+        let (first, second) = first.try_switch(second.unwrap(), &rules).unwrap();
+        assert_eq!(vec![Card::new(Suit::Clubs, Rank::Ace), Card::new(Suit::Hearts, Rank::King)], first.cards());
+        assert_eq!(vec![Card::new(Suit::Hearts, Rank::Five), Card::new(Suit::Clubs, Rank::Six)], second.cards());
+
+        assert_eq!(Outcome::Win, settle(&first, &dealer, &rules));
+        assert_eq!(Outcome::Lose, settle(&second.stand(), &dealer, &rules));
+    }
+
+    #[test]
+    fn free_bets_only_pay() {
+        let rules = RuleSet::free_bet_game();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Eight),
+            ]
+        ).stand();
+
+        let eleven = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Six),
+                Card::new(Suit::Clubs, Rank::Five),
+            ]
+        );
+        let doubled = eleven.clone().try_free_double_down(Card::new(Suit::Hearts, Rank::Nine), &rules).unwrap();
+        assert_eq!(1, doubled.free_units());
+        assert_eq!(Outcome::Win, settle(&doubled, &dealer, &rules));
+        assert_eq!(2.0, net_units(&doubled, Outcome::Win, &rules));
+        assert_eq!(-1.0, net_units(&doubled, Outcome::Lose, &rules));
+
+        let paid = eleven.try_double_down(Card::new(Suit::Hearts, Rank::Two), &rules).unwrap();
+        assert_eq!(-2.0, net_units(&paid, settle(&paid, &dealer, &rules), &rules));
+
+        let (left, right) = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Eight),
+                Card::new(Suit::Hearts, Rank::Eight),
+            ]
+        ).try_free_split(Card::new(Suit::Spades, Rank::Two), Card::new(Suit::Spades, Rank::Three), &rules, 1).unwrap() {
+            SplitHands::Hands(left, right) => (left, right),
+            SplitHands::Aces(..) => panic!("eights are not aces"),
+        };
+        assert_eq!((0, 1), (left.free_units(), right.free_units()));
+        assert_eq!(-1.0, net_units(&left.stand(), Outcome::Lose, &rules));
+        assert_eq!(0.0, net_units(&right.stand(), Outcome::Lose, &rules));
+
+        let tens = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::King),
+                Card::new(Suit::Hearts, Rank::King),
+            ]
+        );
+        assert!(tens.try_free_split(Card::new(Suit::Spades, Rank::Two), Card::new(Suit::Spades, Rank::Three), &rules, 1).is_err());
+    }
+
+    #[cfg(feature = "variant-spanish21")]
+    #[test]
+    fn spanish_21_always_wins() {
+        let rules = RuleSet::spanish_21_game();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Hearts, Rank::King),
+                Card::new(Suit::Hearts, Rank::Five),
+            ]
+        );
+        let dealer = match dealer.hit(Card::new(Suit::Hearts, Rank::Six)) {
+            Hit::Live(hand) => hand.stand(),
+            Hit::Bust(_) => panic!("21 is not bust"),
+        };
+
+        let sevens = |suits: [Suit; 3]| match Hand::new(
+            vec![
+                Card::new(suits[0], Rank::Seven),
+                Card::new(suits[1], Rank::Seven),
+            ]
+        ).hit(Card::new(suits[2], Rank::Seven)) {
+            Hit::Live(hand) => hand.stand(),
+            Hit::Bust(_) => panic!("21 is not bust"),
+        };
+
+        let mixed = sevens([Suit::Clubs, Suit::Hearts, Suit::Spades]);
+        assert_eq!(Outcome::Win, settle(&mixed, &dealer, &rules));
+        assert_eq!(Outcome::Push, settle(&mixed, &dealer, &RuleSet::builder().build().unwrap()));
+        assert_eq!(1.5, net_units(&mixed, Outcome::Win, &rules));
+
+        let spades = sevens([Suit::Spades; 3]);
+        assert_eq!(Some(3.0), bonus_multiplier(&spades, &rules));
+        assert_eq!(Some(2.0), bonus_multiplier(&sevens([Suit::Hearts; 3]), &rules));
+        assert_eq!(None, bonus_multiplier(&spades, &RuleSet::builder().build().unwrap()));
+    }
+
+    #[test]
+    fn settlement_policy_knobs() {
+        let rules = |policy: SettlementPolicy| RuleSet::builder()
+            .settlement(policy)
+            .build()
+            .unwrap();
+
+        let natural = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ace),
+                Card::new(Suit::Spades, Rank::King),
+            ]
+        );
+
+        let (split_21, _) = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::King),
+                Card::new(Suit::Hearts, Rank::King),
+            ]
+        ).split(Card::new(Suit::Clubs, Rank::Ace), Card::new(Suit::Hearts, Rank::Two)) {
+            SplitHands::Hands(left, right) => (left.stand(), right),
+            SplitHands::Aces(..) => panic!("kings are not aces"),
+        };
+        assert_eq!(Outcome::Lose, settle(&split_21, &natural, &rules(SettlementPolicy::new(false, false, false))));
+        assert_eq!(Outcome::Push, settle(&split_21, &natural, &rules(SettlementPolicy::new(false, true, false))));
+
+        let mut five = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Two),
+                Card::new(Suit::Clubs, Rank::Three),
+            ]
+        );
+        for rank in [Rank::Four, Rank::Two, Rank::Seven] {
+            five = match five.hit(Card::new(Suit::Hearts, rank)) {
+                Hit::Live(hand) => hand,
+                Hit::Bust(_) => panic!("18 is not bust"),
+            };
+        }
+        let eighteen = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Eight),
+            ]
+        ).stand();
+        assert_eq!(Outcome::Push, settle(&five, &eighteen, &rules(SettlementPolicy::new(false, false, false))));
+        assert_eq!(Outcome::Win, settle(&five, &eighteen, &rules(SettlementPolicy::new(false, false, true))));
+    }
+
+    #[cfg(feature = "variant-spanish21")]
+    #[test]
+    fn suited_blackjack_bonus() {
+        let rules = RuleSet::builder()
+            .bonuses(Some(Paytable::new(vec![(Bonus::SuitedBlackjack, 2.0), (Bonus::SevenSevenSeven, 5.0)]).unwrap()))
+            .build()
+            .unwrap();
+
+        let dealer = Hand::new(
+            vec![
+                Card::new(Suit::Spades, Rank::Ten),
+                Card::new(Suit::Spades, Rank::Nine),
+            ]
+        ).stand();
+
+        let natural = |second: Suit| Hand::new(
+            vec![
+                Card::new(Suit::Hearts, Rank::Ace),
+                Card::new(second, Rank::King),
+            ]
+        );
+        assert_eq!(2.0, net_units(&natural(Suit::Hearts), settle(&natural(Suit::Hearts), &dealer, &rules), &rules));
+        assert_eq!(1.5, net_units(&natural(Suit::Clubs), settle(&natural(Suit::Clubs), &dealer, &rules), &rules));
+
+        // suited sevens still qualify for the plain 7-7-7 entry
+        let sevens = match Hand::new(
+            vec![
+                Card::new(Suit::Hearts, Rank::Seven),
+                Card::new(Suit::Hearts, Rank::Seven),
+            ]
+        ).hit(Card::new(Suit::Hearts, Rank::Seven)) {
+            Hit::Live(hand) => hand.stand(),
+            Hit::Bust(_) => panic!("21 is not bust"),
+        };
+        assert_eq!(Some(5.0), bonus_multiplier(&sevens, &rules));
+    }
+
+    #[test]
+    fn initial_deal_order() {
+        let rules = RuleSet::builder().players(2).build().unwrap();
+        let mut deck = Deck::new_shoe(1);
+        let order: Vec<Card> = deck.stack().iter().copied().rev().take(6).collect();
+
+        let deal = deal_initial(&mut deck, &rules, &mut StdRng::seed_from_u64(0)).unwrap();
+        assert_eq!(46, deck.cards_left());
+        assert_eq!(2, deal.seats().len());
+        assert_eq!(vec![order[0], order[3]], deal.seats()[0].cards());
+        assert_eq!(vec![order[1], order[4]], deal.seats()[1].cards());
+        assert_eq!(vec![order[2], order[5]], deal.dealer().cards());
+        assert_eq!((HandId::seat(1), HandId::DEALER), (deal.seats()[1].id(), deal.dealer().id()));
+    }
+
+    #[test]
+    fn rotating_shoes_are_faster() {
+        let rules = RuleSet::builder().build().unwrap();
+
+        assert!(TableTiming::rotating_shoes().rounds_per_hour(&rules) > TableTiming::hand_shuffled().rounds_per_hour(&rules));
+        assert!(TableTiming::rotating_shoes().rounds_per_hour(&rules) < TableTiming::csm().rounds_per_hour(&rules));
+    }
+
+    #[test]
+    fn no_hole_card_losses() {
+        let rules = |loss: EnhcLoss| RuleSet::builder()
+            .no_hole_card(Some(loss))
+            .build()
+            .unwrap();
+
+        // split eights, doubled the first and busted the second
+        let (left, right) = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Eight),
+                Card::new(Suit::Hearts, Rank::Eight),
+            ]
+        ).split(Card::new(Suit::Spades, Rank::Three), Card::new(Suit::Spades, Rank::Six)) {
+            SplitHands::Hands(left, right) => (left, right),
+            SplitHands::Aces(..) => panic!("eights are not aces"),
+        };
+        let doubled = left.double_down(Card::new(Suit::Diamonds, Rank::Two));
+        let busted = match right.hit(Card::new(Suit::Diamonds, Rank::King)) {
+            Hit::Bust(hand) => hand,
+            Hit::Live(_) => panic!("24 is bust"),
+        };
+
+        let stakes = [HandStake::of(&doubled), HandStake::of(&busted)];
+        assert_eq!((HandId::seat(0).left(), HandId::seat(0).right()), (stakes[0].id(), stakes[1].id()));
+        assert_eq!(3.0, dealer_natural_loss(&stakes, &rules(EnhcLoss::AllBets)));
+        assert_eq!(1.0, dealer_natural_loss(&stakes, &rules(EnhcLoss::OriginalBetsOnly)));
+        assert_eq!(2.0, dealer_natural_loss(&stakes, &rules(EnhcLoss::BustedBetsPlusOne)));
+
+        // settled hand by hand against the natural, the split and doubled money comes back
+        let dealer = Hand::new(vec![Card::new(Suit::Spades, Rank::Ace), Card::new(Suit::Spades, Rank::King)]).stand();
+        assert_eq!(Outcome::Lose, settle(&doubled, &dealer, &rules(EnhcLoss::OriginalBetsOnly)));
+        for (loss, expected) in [(EnhcLoss::AllBets, [-2.0, -1.0]), (EnhcLoss::OriginalBetsOnly, [-1.0, 0.0]), (EnhcLoss::BustedBetsPlusOne, [-1.0, -1.0])] {
+            let rules = rules(loss);
+            assert_eq!(expected, [settle_units(&doubled, &dealer, &rules), settle_units(&busted, &dealer, &rules)]);
+            assert_eq!(-dealer_natural_loss(&stakes, &rules), expected.iter().sum::<f64>());
+        }
+
+        let beaten = Hand::new(vec![Card::new(Suit::Spades, Rank::Ten), Card::new(Suit::Spades, Rank::Nine)]).stand();
+        assert_eq!(-2.0, settle_units(&doubled, &beaten, &rules(EnhcLoss::OriginalBetsOnly)));
+
+        let settled = settle_hand(&busted, &dealer, &rules(EnhcLoss::BustedBetsPlusOne));
+        assert_eq!((HandId::seat(0).right(), Outcome::Lose, -1.0), (settled.id(), settled.outcome(), settled.units()));
+    }
+}
@@ -1,12 +1,117 @@
-use crate::{card::{Card, Rank}, rule::RuleSet};
+use std::{cmp::Ordering, error::Error, fmt, hash::{Hash, Hasher}, ops::Deref, str::FromStr};
+use crate::{card::{Card, CardError, Rank, Suit}, game::{settle, Outcome}, rule::RuleSet};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
 pub struct Hand<S: HandState> {
-    stack: Vec<Card>,
+    stack: CardStack,
+    // every ace as one
+    total: u64,
+    aces: u8,
+    origin: HandOrigin,
+    id: HandId,
+    free_units: u8,
     marker: std::marker::PhantomData<S>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HandOrigin {
+    Dealt,
+    Split,
+    #[cfg(feature = "variant-switch")]
+    Switched,
+}
+
+// names a hand for settlement records and logs: the seat it was dealt to, and where it sits in that seat's split
+// tree, numbered like a binary heap so the hands from a split get ids without asking what else is on the table
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(try_from = "HandIdRecord"))]
+pub struct HandId {
+    seat: u8,
+    path: u32,
+}
+
+// the split path starts at 1, so a saved 0 never came from a real hand
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct HandIdRecord {
+    seat: u8,
+    path: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<HandIdRecord> for HandId {
+    type Error = HandError;
+
+    fn try_from(record: HandIdRecord) -> Result<Self, Self::Error> {
+        match record.path {
+            0 => Err(HandError::InvalidId),
+            path => Ok(Self { seat: record.seat, path }),
+        }
+    }
+}
+
+impl HandId {
+    // the dealer's hand takes the seat no player can have
+    pub const DEALER: Self = Self { seat: u8::MAX, path: 1 };
+
+    // players sit in seats 0 to 254
+    pub const MAX_SEATS: usize = u8::MAX as usize;
+
+    pub fn seat(seat: u8) -> Self {
+        Self { seat, path: 1 }
+    }
+
+    pub fn seat_index(&self) -> u8 {
+        self.seat
+    }
+
+    // how many splits this hand came through
+    pub fn splits(&self) -> u32 {
+        self.path.ilog2()
+    }
+
+    // the original wager stays left
+    pub fn left(&self) -> Self {
+        Self { seat: self.seat, path: self.path << 1 }
+    }
+
+    pub fn right(&self) -> Self {
+        Self { seat: self.seat, path: self.path << 1 | 1 }
+    }
+
+    // every split leaves the original wager on the left hand
+    pub fn keeps_original_bet(&self) -> bool {
+        self.path.is_power_of_two()
+    }
+
+    // the hand this one was split from
+    pub fn parent(&self) -> Option<Self> {
+        (self.path > 1).then_some(Self { seat: self.seat, path: self.path >> 1 })
+    }
+}
+
+impl fmt::Display for HandId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::DEALER => write!(f, "dealer"),
+            Self { seat, path } => {
+                write!(f, "seat {seat}")?;
+                // one L or R per split, from the dealt hand down
+                for depth in (0..self.splits()).rev() {
+                    write!(f, "{}", if path >> depth & 1 == 0 { 'L' } else { 'R' })?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HandValue {
     Hard(u64),
     Soft {
@@ -15,211 +120,1231 @@ pub enum HandValue {
     }
 }
 
-impl<S> Hand<S>
+impl HandValue {
+    // the highest total that does not bust, or the bust total
+    pub fn best(&self) -> u64 {
+        match *self {
+            Self::Hard(v) => v,
+            Self::Soft { lower, upper } => if upper > 21 { lower } else { upper },
+        }
+    }
+
+    // every ace counted as one
+    pub fn total(&self) -> u64 {
+        match *self {
+            Self::Hard(v) => v,
+            Self::Soft { lower, .. } => lower,
+        }
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.best() > 21
+    }
+
+    // soft only while the ace can still count as eleven, so A,T,5 is hard 16 rather than soft 16/26
+    pub fn effective(&self) -> Self {
+        match *self {
+            Self::Soft { lower, upper } if upper > 21 => Self::Hard(lower),
+            value => value,
+        }
+    }
+
+    // which total wins a showdown: every bust ties at the bottom, and soft and hard totals of the same number tie
+    pub fn cmp_blackjack(&self, other: &Self) -> Ordering {
+        let rank = |value: &Self| if value.is_bust() { 0 } else { value.best() };
+
+        rank(self).cmp(&rank(other))
+    }
+}
+
+// blackjack order first; ties are broken by bust total, then hard before soft, only so that the order agrees
+// with Eq
+impl Ord for HandValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let soft = |value: &Self| matches!(value, Self::Soft { .. });
+
+        self.cmp_blackjack(other)
+            .then(self.best().cmp(&other.best()))
+            .then(soft(self).cmp(&soft(other)))
+            .then(self.total().cmp(&other.total()))
+    }
+}
+
+impl PartialOrd for HandValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Hand<S>
+    where S: HandState
+{
+    pub fn cards(&self) -> &[Card] {
+        &self.stack
+    }
+
+    pub fn origin(&self) -> HandOrigin {
+        self.origin
+    }
+
+    pub fn id(&self) -> HandId {
+        self.id
+    }
+
+    // seats a hand at the table; hands split from it inherit the seat
+    pub fn with_id(self, id: HandId) -> Self {
+        Self { id, ..self }
+    }
+
+    // units of this hand's wager put up by the house with free bet tokens
+    pub fn free_units(&self) -> u8 {
+        self.free_units
+    }
+
+    // the same as `settle`, which owns the bust, natural and tie rules
+    pub fn compare_to_dealer<D>(&self, dealer: &Hand<D>, rules: &RuleSet) -> Outcome
+        where D: HandState
+    {
+        settle(self, dealer, rules)
+    }
+
+    // still the original two dealt cards
+    pub fn is_fresh(&self) -> bool {
+        self.origin == HandOrigin::Dealt && self.stack.len() == 2
+    }
+
+    // kept up as cards arrive, so this never walks the cards
+    pub fn value(&self) -> HandValue {
+        match self.aces {
+            0 => HandValue::Hard(self.total),
+            _ => HandValue::Soft { lower: self.total, upper: self.total + 10 },
+        }
+    }
+
+    pub fn effective_value(&self) -> HandValue {
+        self.value().effective()
+    }
+}
+
+// just the rank counts of a hand: Copy and allocation free, for strategy tables and EV recursion that never
+// look at suits or card order
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct CompactHand {
+    counts: [u8; 13],
+    // every ace as one
+    total: u8,
+    cards: u8,
+}
+
+impl CompactHand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, rank: Rank) {
+        self.counts[rank as usize] += 1;
+        self.total += pip_value(rank) as u8;
+        self.cards += 1;
+    }
+
+    pub fn with(self, rank: Rank) -> Self {
+        let mut hand: Self = self;
+        hand.add(rank);
+
+        hand
+    }
+
+    pub fn count(&self, rank: Rank) -> u8 {
+        self.counts[rank as usize]
+    }
+
+    pub fn cards(&self) -> u8 {
+        self.cards
+    }
+
+    pub fn value(&self) -> HandValue {
+        let total: u64 = self.total as u64;
+
+        match self.count(Rank::Ace) {
+            0 => HandValue::Hard(total),
+            _ => HandValue::Soft { lower: total, upper: total + 10 },
+        }
+    }
+
+    pub fn effective_value(&self) -> HandValue {
+        self.value().effective()
+    }
+
+    pub fn is_pair(&self) -> bool {
+        self.cards == 2 && self.counts.contains(&2)
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.value().is_bust()
+    }
+}
+
+impl<S> From<&Hand<S>> for CompactHand
+    where S: HandState
+{
+    fn from(hand: &Hand<S>) -> Self {
+        hand.stack.iter().fold(Self::new(), |compact, card| compact.with(card.rank()))
+    }
+}
+
+// aces count one here
+fn pip_value(rank: Rank) -> u64 {
+    match rank {
+        Rank::Ace => 1,
+        Rank::Two => 2,
+        Rank::Three => 3,
+        Rank::Four => 4,
+        Rank::Five => 5,
+        Rank::Six => 6,
+        Rank::Seven => 7,
+        Rank::Eight => 8,
+        Rank::Nine => 9,
+        Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => 10,
+    }
+}
+
+// twenty-one aces and the card that busts them; no hand that can still be hit holds more
+const MAX_CARDS: usize = 22;
+
+// a hand's cards kept inline, so dealing and splitting never allocate
+#[derive(Clone, Copy)]
+struct CardStack {
+    cards: [Card; MAX_CARDS],
+    len: u8,
+}
+
+impl CardStack {
+    fn new() -> Self {
+        Self { cards: [Card::new(Suit::Clubs, Rank::Ace); MAX_CARDS], len: 0 }
+    }
+
+    fn push(&mut self, card: Card) {
+        assert!((self.len as usize) < MAX_CARDS, "a hand holds at most {MAX_CARDS} cards");
+
+        self.cards[self.len as usize] = card;
+        self.len += 1;
+    }
+}
+
+impl Deref for CardStack {
+    type Target = [Card];
+
+    fn deref(&self) -> &[Card] {
+        &self.cards[..self.len as usize]
+    }
+}
+
+// the unused slots are filler, so everything below looks only at the dealt cards
+impl fmt::Debug for CardStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for CardStack {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for CardStack {}
+
+impl PartialOrd for CardStack {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CardStack {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl Hash for CardStack {
+    fn hash<H>(&self, state: &mut H)
+        where H: Hasher
+    {
+        (**self).hash(state);
+    }
+}
+
+// the same list of cards a Vec would have written
+#[cfg(feature = "serde")]
+impl Serialize for CardStack {
+    fn serialize<Z>(&self, serializer: Z) -> Result<Z::Ok, Z::Error>
+        where Z: serde::Serializer
+    {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CardStack {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        let cards: Vec<Card> = Vec::deserialize(deserializer)?;
+        if cards.len() > MAX_CARDS {
+            return Err(serde::de::Error::invalid_length(cards.len(), &"at most 22 cards"));
+        }
+
+        let mut stack = Self::new();
+        for card in cards {
+            stack.push(card);
+        }
+
+        Ok(stack)
+    }
+}
+
+// what a hand is saved as: the total and ace count are rebuilt from the cards, and the state has to match the
+// type it is loaded into
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct HandRecord {
+    state: HandStateKind,
+    cards: CardStack,
+    origin: HandOrigin,
+    id: HandId,
+    free_units: u8,
+}
+
+#[cfg(feature = "serde")]
+impl<S> Serialize for Hand<S>
+    where S: HandState
+{
+    fn serialize<Z>(&self, serializer: Z) -> Result<Z::Ok, Z::Error>
+        where Z: serde::Serializer
+    {
+        let record = HandRecord { state: S::KIND, cards: self.stack, origin: self.origin, id: self.id, free_units: self.free_units };
+
+        record.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, S> Deserialize<'de> for Hand<S>
+    where S: HandState
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        let record: HandRecord = HandRecord::deserialize(deserializer)?;
+        if record.state != S::KIND {
+            return Err(serde::de::Error::custom(HandError::WrongState));
+        }
+
+        let mut hand: Self = Self::from_stack(record.cards.iter().copied(), record.origin).with_id(record.id);
+        hand.free_units = record.free_units;
+
+        if !hand.fits_state() {
+            return Err(serde::de::Error::custom(HandError::ImpossibleState));
+        }
+
+        Ok(hand)
+    }
+}
+
+// a dealt hand from cards like "As Td"
+impl FromStr for Hand<Fresh> {
+    type Err = CardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cards: Vec<Card> = s.split_whitespace().map(str::parse).collect::<Result<Vec<Card>, CardError>>()?;
+
+        if cards.is_empty() || cards.len() > MAX_CARDS {
+            return Err(CardError::ParseCardError);
+        }
+
+        Ok(Self::new(cards))
+    }
+}
+
+// panics on a malformed card, so for tests and scenarios
+#[macro_export]
+macro_rules! hand {
+    ($cards:expr) => {
+        $crate::hand::Hand::new($crate::cards!($cards))
+    };
+}
+
+impl IsTerminal for Hand<Fresh> {
+    fn is_terminal() -> bool {
+        false
+    }
+}
+
+impl<S> HandInfo for Hand<S>
+    where S: HandState
+{
+    fn is_21(&self) -> bool {
+        match self.value() {
+            HandValue::Hard(v) => v == 21,
+            HandValue::Soft { lower, upper } => lower == 21 || upper == 21,
+        }
+    }
+
+    fn is_natural(&self) -> bool {
+        self.is_fresh() && self.is_21()
+    }
+
+    fn is_bust(&self) -> bool {
+        self.value().is_bust()
+    }
+
+    fn is_pair(&self) -> bool {
+        self.stack.len() == 2 && self.stack[0].rank() == self.stack[1].rank()
+    }
+
+    fn is_pair_of(&self, rank: Rank) -> bool {
+        self.is_pair() && self.stack[0].rank() == rank
+    }
+
+    fn is_soft(&self) -> bool {
+        matches!(self.effective_value(), HandValue::Soft { .. })
+    }
+
+    fn is_hard(&self) -> bool {
+        !self.is_soft()
+    }
+
+    fn is_blackjack(&self) -> bool {
+        self.is_natural()
+    }
+
+    fn is_charlie(&self, rules: &RuleSet) -> bool {
+        match rules.charlie() {
+            Some(n) => self.stack.len() >= n as usize && !self.is_bust(),
+            None => false,
+        }
+    }
+
+    fn can_hit(&self, rules: &RuleSet) -> bool {
+        if self.is_bust() || !S::can_transition_to(S::KIND) {
+            return false;
+        }
+
+        match S::KIND {
+            // split aces only receive the one card dealt with the split unless the policy allows hits
+            HandStateKind::SplitAces => self.stack.len() - 2 < rules.split_aces().hits_allowed() as usize,
+            _ => true,
+        }
+    }
+
+    fn can_split(&self, rules: &RuleSet, hands: u64) -> bool {
+        if !self.is_split_pair(rules) || hands >= rules.split_rules().max_hands(self.stack[0].rank()) {
+            return false;
+        }
+
+        match S::KIND {
+            HandStateKind::SplitAces => rules.split_aces().resplit(),
+            _ => self.can_transition_to(HandStateKind::Split) || self.can_transition_to(HandStateKind::SplitAces),
+        }
+    }
+
+    fn split_refusal(&self, rules: &RuleSet, hands: u64) -> Option<HandError> {
+        if !self.is_split_pair(rules) {
+            return Some(HandError::NotAPair);
+        }
+
+        if hands >= rules.split_rules().max_hands(self.stack[0].rank()) {
+            return Some(HandError::MaxHandsReached);
+        }
+
+        if self.can_split(rules, hands) {
+            return None;
+        }
+
+        match S::KIND {
+            HandStateKind::SplitAces => Some(HandError::ResplitNotAllowed),
+            _ => Some(HandError::SplitNotAllowed),
+        }
+    }
+
+    fn can_surrender(&self, rules: &RuleSet, upcard: Card) -> bool {
+        if !rules.surrender().allows(upcard.rank()) || !self.can_transition_to(HandStateKind::Surrender) {
+            return false;
+        }
+
+        // early surrender comes before the peek, so before any split
+        if rules.surrender().is_early() {
+            return self.origin == HandOrigin::Dealt;
+        }
+
+        self.origin != HandOrigin::Split || rules.surrender_policy().after_split()
+    }
+
+    fn can_double_down(&self, rules: &RuleSet) -> bool {
+        if !self.can_transition_to(HandStateKind::DoubleDown) {
+            return false;
+        }
+
+        if self.origin == HandOrigin::Split && !rules.das().allows(self.effective_value()) {
+            return false;
+        }
+
+        rules.double_rule().allows(self.effective_value(), self.stack.len())
+    }
+
+    fn can_free_double_down(&self, rules: &RuleSet) -> bool {
+        rules.free_bet()
+            && self.stack.len() == 2
+            && matches!(self.value(), HandValue::Hard(9..=11))
+            && self.can_double_down(rules)
+    }
+
+    fn can_free_split(&self, rules: &RuleSet, hands: u64) -> bool {
+        rules.free_bet()
+            && self.is_pair()
+            && !matches!(self.stack[0].rank(), Rank::Ten | Rank::Jack | Rank::Queen | Rank::King)
+            && self.can_split(rules, hands)
+    }
+}
+
+impl Hand<Fresh> {
+    // panics past 22 cards, more than any hand that can still be played holds
+    pub fn new<I>(cards: I) -> Self
+        where I: IntoIterator<Item = Card>
+    {
+        Self::from_stack(cards, HandOrigin::Dealt)
+    }
+
+    // blackjack switch: trades the second cards of two dealt hands; a 21 made this way is not a natural. A refusal
+    // hands both hands back
+    #[cfg(feature = "variant-switch")]
+    pub fn try_switch(self, other: Hand<Fresh>, rules: &RuleSet) -> Result<(Hand<Fresh>, Hand<Fresh>), Rejected<Fresh>> {
+        if !rules.switch() || !self.is_fresh() || !other.is_fresh() {
+            return Err(Rejected { hand: self, partner: Some(Box::new(other)), error: HandError::SwitchNotAllowed });
+        }
+
+        Ok((
+            Hand::from_stack([self.stack[0], other.stack[1]], HandOrigin::Switched).with_id(self.id),
+            Hand::from_stack([other.stack[0], self.stack[1]], HandOrigin::Switched).with_id(other.id),
+        ))
+    }
+
+}
+
+impl<S> Hand<S>
+    where S: Playable
+{
+    // unchecked; outside the crate surrender goes through `try_surrender`
+    pub(crate) fn surrender(self) -> Hand<Surrender> {
+        self.transition()
+    }
+
+    pub fn try_surrender(self, rules: &RuleSet, upcard: Card) -> Result<Hand<Surrender>, Rejected<S>> {
+        if !self.can_surrender(rules, upcard) {
+            return Err(Rejected::new(self, HandError::SurrenderNotAllowed));
+        }
+
+        Ok(self.surrender())
+    }
+
+    pub fn hit(self, card: Card) -> Hit<S> {
+        let mut hand = self;
+        hand.push(card);
+
+        if hand.is_bust() {
+            return Hit::Bust(hand.transition());
+        }
+
+        Hit::Live(hand.transition())
+    }
+
+    pub fn stand(self) -> Hand<Stand> {
+        self.transition()
+    }
+
+    // unchecked like `surrender`; see `try_double_down`
+    pub(crate) fn double_down(self, card: Card) -> Hand<DoubleDown> {
+        let mut hand: Hand<DoubleDown> = self.transition();
+        hand.push(card);
+
+        hand
+    }
+
+    // unchecked like `surrender`; see `try_split`
+    pub(crate) fn split(self, first: Card, second: Card) -> SplitHands {
+        self.split_pair(first, second)
+    }
+
+    pub fn try_double_down(self, card: Card, rules: &RuleSet) -> Result<Hand<DoubleDown>, Rejected<S>> {
+        if !self.can_double_down(rules) {
+            return Err(Rejected::new(self, HandError::DoubleDownNotAllowed));
+        }
+
+        Ok(self.double_down(card))
+    }
+
+    pub fn try_split(self, first: Card, second: Card, rules: &RuleSet, hands: u64) -> Result<SplitHands, Rejected<S>> {
+        if let Some(error) = self.split_refusal(rules, hands) {
+            return Err(Rejected::new(self, error));
+        }
+
+        Ok(self.split(first, second))
+    }
+
+    pub fn try_free_double_down(self, card: Card, rules: &RuleSet) -> Result<Hand<DoubleDown>, Rejected<S>> {
+        if !self.can_free_double_down(rules) {
+            return Err(Rejected::new(self, HandError::FreeBetNotAllowed));
+        }
+
+        let mut hand: Hand<DoubleDown> = self.double_down(card);
+        hand.free_units += 1;
+
+        Ok(hand)
+    }
+
+    // the new right hand is played entirely on a free bet token
+    pub fn try_free_split(self, first: Card, second: Card, rules: &RuleSet, hands: u64) -> Result<SplitHands, Rejected<S>> {
+        if !self.can_free_split(rules, hands) {
+            return Err(Rejected::new(self, HandError::FreeBetNotAllowed));
+        }
+
+        Ok(match self.split(first, second) {
+            SplitHands::Hands(left, mut right) => {
+                right.free_units = 1;
+                SplitHands::Hands(left, right)
+            },
+            SplitHands::Aces(left, mut right) => {
+                right.free_units = 1;
+                SplitHands::Aces(left, right)
+            },
+        })
+    }
+}
+
+impl Hand<SplitAces> {
+    pub fn hits_taken(&self) -> usize {
+        self.stack.len() - 2
+    }
+
+    pub fn try_hit(self, card: Card, rules: &RuleSet) -> Result<Hit<SplitAces>, Rejected<SplitAces>> {
+        if !self.can_hit(rules) {
+            return Err(Rejected::new(self, HandError::HitNotAllowed));
+        }
+
+        let mut hand = self;
+        hand.push(card);
+
+        if hand.is_bust() {
+            return Ok(Hit::Bust(hand.transition()));
+        }
+
+        Ok(Hit::Live(hand.transition()))
+    }
+
+    pub fn try_split(self, first: Card, second: Card, rules: &RuleSet, hands: u64) -> Result<SplitHands, Rejected<SplitAces>> {
+        if let Some(error) = self.split_refusal(rules, hands) {
+            return Err(Rejected::new(self, error));
+        }
+
+        Ok(self.split_pair(first, second))
+    }
+
+    pub fn stand(self) -> Hand<SplitAcesLocked> {
+        self.transition()
+    }
+}
+
+impl<S> Hand<S>
+    where S: HandState
+{
+    fn from_stack<I>(cards: I, origin: HandOrigin) -> Self
+        where I: IntoIterator<Item = Card>
+    {
+        let mut hand = Self {
+            stack: CardStack::new(),
+            total: 0,
+            aces: 0,
+            origin,
+            id: HandId::seat(0),
+            free_units: 0,
+            marker: std::marker::PhantomData::<S>,
+        };
+
+        for card in cards {
+            hand.push(card);
+        }
+
+        hand
+    }
+
+    fn push(&mut self, card: Card) {
+        self.stack.push(card);
+        self.total += pip_value(card.rank());
+
+        if card.rank() == Rank::Ace {
+            self.aces += 1;
+        }
+    }
+
+    pub fn state(&self) -> HandStateKind {
+        S::KIND
+    }
+
+    // whether play could have left these cards in state `S`; only loaded hands can fail this
+    #[cfg(feature = "serde")]
+    fn fits_state(&self) -> bool {
+        let cards: usize = self.stack.len();
+        let split = self.origin == HandOrigin::Split;
+        let split_aces = split && self.stack.first().is_some_and(|c| c.rank() == Rank::Ace);
+
+        cards >= 2 && match S::KIND {
+            HandStateKind::Fresh => !split && !self.is_bust(),
+            HandStateKind::Bust => self.is_bust(),
+            // the one doubled card can bust the hand without moving it to Bust
+            HandStateKind::DoubleDown => cards >= 3,
+            HandStateKind::Split => split && !self.is_bust(),
+            HandStateKind::SplitAces | HandStateKind::SplitAcesLocked => split_aces && !self.is_bust(),
+            HandStateKind::Stand => !self.is_bust(),
+            HandStateKind::Surrender => cards == 2,
+        }
+    }
+
+    pub fn can_transition_to(&self, next: HandStateKind) -> bool {
+        if !S::can_transition_to(next) {
+            return false;
+        }
+
+        match next {
+            HandStateKind::Bust => self.is_bust(),
+            HandStateKind::DoubleDown => self.stack.len() >= 2,
+            HandStateKind::Surrender => self.stack.len() == 2,
+            HandStateKind::Split if S::KIND != next => (self.is_pair() || self.is_ten_pair()) && self.stack[0].rank() != Rank::Ace,
+            HandStateKind::SplitAces if S::KIND != next => self.is_pair() && self.stack[0].rank() == Rank::Ace,
+            _ => true,
+        }
+    }
+
+    // every public way in checks legality first, through the type or a try_* method, so this only guards the crate
+    fn transition<N: HandState>(self) -> Hand<N> {
+        debug_assert!(
+            self.can_transition_to(N::KIND),
+            "illegal hand transition from {:?} to {:?}", S::KIND, N::KIND,
+        );
+
+        Hand {
+            stack: self.stack,
+            total: self.total,
+            aces: self.aces,
+            origin: self.origin,
+            id: self.id,
+            free_units: self.free_units,
+            marker: std::marker::PhantomData::<N>,
+        }
+    }
+
+    // two ten-value cards of different ranks, splittable only under some rules
+    fn is_ten_pair(&self) -> bool {
+        let ten = |c: &Card| matches!(c.rank(), Rank::Ten | Rank::Jack | Rank::Queen | Rank::King);
+
+        self.stack.len() == 2 && self.stack.iter().all(ten)
+    }
+
+    fn is_split_pair(&self, rules: &RuleSet) -> bool {
+        self.is_pair() || (rules.split_rules().unequal_tens() && self.is_ten_pair())
+    }
+
+    fn split_pair(self, first: Card, second: Card) -> SplitHands {
+        debug_assert!(self.is_pair() || self.is_ten_pair(), "can only split a pair");
+
+        let left: [Card; 2] = [self.stack[0], first];
+        let right: [Card; 2] = [self.stack[1], second];
+
+        // the original wager stays with the left hand
+        if self.stack[0].rank() == Rank::Ace {
+            debug_assert!(
+                S::can_transition_to(HandStateKind::SplitAces),
+                "illegal hand transition from {:?} to {:?}", S::KIND, HandStateKind::SplitAces,
+            );
+
+            let mut left: Hand<SplitAces> = Hand::from_stack(left, HandOrigin::Split).with_id(self.id.left());
+            left.free_units = self.free_units;
+
+            return SplitHands::Aces(left, Hand::from_stack(right, HandOrigin::Split).with_id(self.id.right()));
+        }
+
+        debug_assert!(
+            S::can_transition_to(HandStateKind::Split),
+            "illegal hand transition from {:?} to {:?}", S::KIND, HandStateKind::Split,
+        );
+
+        let mut left: Hand<Split> = Hand::from_stack(left, HandOrigin::Split).with_id(self.id.left());
+        left.free_units = self.free_units;
+
+        SplitHands::Hands(left, Hand::from_stack(right, HandOrigin::Split).with_id(self.id.right()))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub enum Hit<S: HandState> {
+    Live(Hand<S>),
+    Bust(Hand<Bust>),
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub enum SplitHands {
+    Hands(Hand<Split>, Hand<Split>),
+    Aces(Hand<SplitAces>, Hand<SplitAces>),
+}
+
+impl IsTerminal for Hand<Bust> {
+    fn is_terminal() -> bool {
+        true
+    }
+}
+
+impl IsTerminal for Hand<DoubleDown> {
+    fn is_terminal() -> bool {
+        true
+    }
+}
+
+impl IsTerminal for Hand<Split> {
+    fn is_terminal() -> bool {
+        false
+    }
+}
+
+impl IsTerminal for Hand<SplitAces> {
+    fn is_terminal() -> bool {
+        false
+    }
+}
+
+impl IsTerminal for Hand<SplitAcesLocked> {
+    fn is_terminal() -> bool {
+        true
+    }
+}
+
+impl IsTerminal for Hand<Stand> {
+    fn is_terminal() -> bool {
+        true
+    }
+}
+
+impl IsTerminal for Hand<Surrender> {
+    fn is_terminal() -> bool {
+        true
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fresh;
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Bust;
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DoubleDown;
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Split;
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SplitAces;
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SplitAcesLocked;
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Stand;
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Surrender;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HandStateKind {
+    Fresh,
+    Bust,
+    DoubleDown,
+    Split,
+    SplitAces,
+    SplitAcesLocked,
+    Stand,
+    Surrender,
+}
+
+impl HandStateKind {
+    pub fn can_transition_to(&self, next: HandStateKind) -> bool {
+        use HandStateKind::*;
+
+        match self {
+            Fresh => matches!(next, Fresh | Bust | DoubleDown | Split | SplitAces | Stand | Surrender),
+            Split => matches!(next, Split | Bust | DoubleDown | Stand | Surrender),
+            SplitAces => matches!(next, SplitAces | Bust | SplitAcesLocked),
+            Bust | DoubleDown | SplitAcesLocked | Stand | Surrender => false,
+        }
+    }
+}
+
+pub trait HandState {
+    const KIND: HandStateKind;
+
+    fn can_transition_to(next: HandStateKind) -> bool {
+        Self::KIND.can_transition_to(next)
+    }
+}
+
+impl HandState for Fresh {
+    const KIND: HandStateKind = HandStateKind::Fresh;
+}
+
+impl HandState for Bust {
+    const KIND: HandStateKind = HandStateKind::Bust;
+}
+
+impl HandState for DoubleDown {
+    const KIND: HandStateKind = HandStateKind::DoubleDown;
+}
+
+impl HandState for Split {
+    const KIND: HandStateKind = HandStateKind::Split;
+}
+
+impl HandState for SplitAces {
+    const KIND: HandStateKind = HandStateKind::SplitAces;
+}
+
+impl HandState for SplitAcesLocked {
+    const KIND: HandStateKind = HandStateKind::SplitAcesLocked;
+}
+
+impl HandState for Stand {
+    const KIND: HandStateKind = HandStateKind::Stand;
+}
+
+impl HandState for Surrender {
+    const KIND: HandStateKind = HandStateKind::Surrender;
+}
+
+// states a player can still act on
+pub trait Playable: HandState {}
+impl Playable for Fresh {}
+impl Playable for Split {}
+
+pub trait IsTerminal {
+    fn is_terminal() -> bool;
+}
+
+pub trait HandInfo {
+    fn is_21(&self) -> bool;
+
+    fn is_natural(&self) -> bool;
+
+    fn is_bust(&self) -> bool;
+
+    fn is_pair(&self) -> bool;
+
+    fn is_pair_of(&self, rank: Rank) -> bool;
+
+    // an ace still counts as 11
+    fn is_soft(&self) -> bool;
+
+    fn is_hard(&self) -> bool;
+
+    // the same as a natural; a 21 made after a split or a switch is not one
+    fn is_blackjack(&self) -> bool;
+
+    fn is_charlie(&self, rules: &RuleSet) -> bool;
+
+    fn can_hit(&self, rules: &RuleSet) -> bool;
+
+    // `hands` is how many hands the player currently holds
+    fn can_split(&self, rules: &RuleSet, hands: u64) -> bool;
+
+    // why `can_split` says no, checked in the order a dealer would explain it
+    fn split_refusal(&self, rules: &RuleSet, hands: u64) -> Option<HandError>;
+    
+    fn can_surrender(&self, rules: &RuleSet, upcard: Card) -> bool;
+
+    fn can_double_down(&self, rules: &RuleSet) -> bool;
+
+    fn can_free_double_down(&self, rules: &RuleSet) -> bool;
+
+    fn can_free_split(&self, rules: &RuleSet, hands: u64) -> bool;
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum HandError {
+    NotAPair,
+    MaxHandsReached,
+    HitNotAllowed,
+    ResplitNotAllowed,
+    SplitNotAllowed,
+    DoubleDownNotAllowed,
+    SurrenderNotAllowed,
+    FreeBetNotAllowed,
+    SwitchNotAllowed,
+    WrongState,
+    ImpossibleState,
+    InvalidId,
+}
+
+impl fmt::Display for HandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAPair => write!(f, "can only split a pair"),
+            Self::MaxHandsReached => write!(f, "maximum number of hands reached"),
+            Self::HitNotAllowed => write!(f, "no more hits allowed on this hand"),
+            Self::ResplitNotAllowed => write!(f, "resplitting not allowed on this hand"),
+            Self::SplitNotAllowed => write!(f, "split not allowed on this hand"),
+            Self::DoubleDownNotAllowed => write!(f, "double down not allowed on this hand"),
+            Self::SurrenderNotAllowed => write!(f, "surrender not allowed on this hand"),
+            Self::FreeBetNotAllowed => write!(f, "free bet not offered on this hand"),
+            Self::SwitchNotAllowed => write!(f, "can only switch two freshly dealt hands at a switch table"),
+            Self::WrongState => write!(f, "hand was saved in a different state"),
+            Self::ImpossibleState => write!(f, "hand's cards could not have reached its saved state"),
+            Self::InvalidId => write!(f, "hand id must have a split path of at least 1"),
+        }
+    }
+}
+
+impl Error for HandError {}
+
+// a rejected action hands the untouched hand back so the caller can fall back to another action
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub struct Rejected<S: HandState> {
+    hand: Hand<S>,
+    // the other hand of a refused switch
+    partner: Option<Box<Hand<S>>>,
+    error: HandError,
+}
+
+impl<S> Rejected<S>
     where S: HandState
 {
-    pub fn value(&self) -> HandValue {
-        let aces = self.stack.iter().filter(|&c| c.rank() == Rank::Ace).count();
-
-        let mut value: u64 = 0;
-
-        if aces == 0 {
-            for c in &self.stack {
-                match c.rank() {
-                    Rank::Ace => panic!("ace found in hard hand"),
-                    Rank::Two => value += 2,
-                    Rank::Three => value += 3,
-                    Rank::Four => value += 4,
-                    Rank::Five => value += 5,
-                    Rank::Six => value += 6,
-                    Rank::Seven => value += 7,
-                    Rank::Eight => value += 8,
-                    Rank::Nine => value += 9,
-                    Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => value += 10,
-                }
-            }
-
-            return HandValue::Hard(value);
-        } else {
-            for c in &self.stack {
-                match c.rank() {
-                    Rank::Ace => value += 1,
-                    Rank::Two => value += 2,
-                    Rank::Three => value += 3,
-                    Rank::Four => value += 4,
-                    Rank::Five => value += 5,
-                    Rank::Six => value += 6,
-                    Rank::Seven => value += 7,
-                    Rank::Eight => value += 8,
-                    Rank::Nine => value += 9,
-                    Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => value += 10,
-                }
-            }
+    fn new(hand: Hand<S>, error: HandError) -> Self {
+        Self { hand, partner: None, error }
+    }
 
-        return HandValue::Soft { lower: value, upper: value + 10 };
-        }
+    pub fn error(&self) -> HandError {
+        self.error
+    }
+
+    pub fn into_hand(self) -> Hand<S> {
+        self.hand
+    }
+
+    pub fn into_hands(self) -> (Hand<S>, Option<Hand<S>>) {
+        (self.hand, self.partner.map(|partner| *partner))
     }
 }
 
-impl IsTerminal for Hand<Fresh> {
-    fn is_terminal() -> bool {
-        false
+impl<S> fmt::Display for Rejected<S>
+    where S: HandState
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
     }
 }
 
-// impl HandInfo for Hand<Fresh> {
-//     fn is_21(&self) -> bool {
-//         match self.value() {
-//             HandValue::Hard(v) => {
-//                 if v == 21 {
-//                     return true;
-//                 }
-
-//                 false
-//             },
-//             HandValue::Soft { lower, upper } => {
-//                 if lower == 21 || upper == 21 {
-//                     return true;
-//                 }
-
-//                 false
-//             },
-//         }
-//     }
-
-//     fn is_natural(&self) -> bool {
-//         if self.is_21() {
-//             return true;
-//         }
-
-//         false
-//     }
-
-//     fn is_bust(&self) -> bool {
-//         match self.value() {
-//             HandValue::Hard(v) => {
-//                 if v > 21 {
-//                     return true;
-//                 }
-
-//                 false
-//             },
-//             HandValue::Soft { lower, upper } => {
-//                 if lower > 21 {
-//                     return true;
-//                 }
-
-//                 false
-//             },
-//         }
-//     }
-
-//     fn is_pair(&self) -> bool {
-//         if self.stack.len() == 2 {
-//             if self.stack[0].rank() == self.stack[0].rank() {
-//                 return true;
-//             }
-//         }
-
-//         false
-//     }
-
-//     fn can_hit(&self, rules: RuleSet) -> bool {
-//         if !self.is_bust() {
-//             return true;
-//         }
-
-//         false
-//     }
-// }
+impl<S> Error for Rejected<S>
+    where S: HandState + fmt::Debug
+{}
 
-impl Hand<Fresh> {
-    pub fn new(stack: Vec<Card>) -> Self {
-        Self {
-            stack,
-            marker: std::marker::PhantomData::<Fresh>,
-        }
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use crate::{card::{Card, CardError, Rank, Suit}, game::Outcome, hand::{CompactHand, Fresh, Hand, HandError, HandId, HandInfo, HandState, HandStateKind, HandValue, Hit, Split, SplitHands, Stand}, rule::{DoubleAfterSplit, DoubleRule, RuleSet, SplitAcesPolicy, SplitRules, SurrenderPolicy, SurrenderRule}};
+
+    #[test]
+    fn hand_value_order() {
+        let soft_17 = HandValue::Soft { lower: 7, upper: 17 };
+        let soft_bust = HandValue::Soft { lower: 23, upper: 33 };
+
+        assert_eq!((17, 7), (soft_17.best(), soft_17.total()));
+        assert_eq!(16, HandValue::Soft { lower: 16, upper: 26 }.best());
+        assert!(soft_bust.is_bust() && !soft_17.is_bust());
+
+        assert_eq!(Ordering::Equal, soft_17.cmp_blackjack(&HandValue::Hard(17)));
+        assert_eq!(Ordering::Equal, HandValue::Hard(22).cmp_blackjack(&soft_bust));
+        assert_eq!(Ordering::Less, HandValue::Hard(30).cmp_blackjack(&HandValue::Hard(4)));
+        assert!(HandValue::Hard(18) > soft_17);
+        assert!(HandValue::Hard(4) > HandValue::Hard(22));
+
+        let mut values = vec![HandValue::Hard(22), soft_17, HandValue::Hard(20), HandValue::Hard(17)];
+        values.sort();
+        assert_eq!(vec![HandValue::Hard(22), HandValue::Hard(17), soft_17, HandValue::Hard(20)], values);
     }
-}
 
-impl IsTerminal for Hand<Bust> {
-    fn is_terminal() -> bool {
-        true
+    #[test]
+    fn cached_values_follow_the_cards() {
+        let mut hand: Hand<Fresh> = crate::hand!("As 2d");
+        for card in crate::cards!("Ah 9c Kd") {
+            hand = match hand.hit(card) {
+                Hit::Live(hand) => hand.transition(),
+                Hit::Bust(bust) => {
+                    assert_eq!(HandValue::Soft { lower: 23, upper: 33 }, bust.value());
+                    return;
+                },
+            };
+            assert_eq!(CompactHand::from(&hand).value(), hand.value());
+        }
+        panic!("A A 2 9 K is bust");
     }
-}
 
-impl IsTerminal for Hand<DoubleDown> {
-    fn is_terminal() -> bool {
-        true
+    #[test]
+    fn compare_to_dealer() {
+        let rules = RuleSet::builder().build().unwrap();
+        let dealer: Hand<Stand> = crate::hand!("As Kd").stand();
+
+        assert_eq!(Outcome::Lose, crate::hand!("7c 7d 7h").stand().compare_to_dealer(&dealer, &rules));
+        assert_eq!(Outcome::Push, crate::hand!("Ac Td").compare_to_dealer(&dealer, &rules));
+        assert_eq!(Outcome::Win, crate::hand!("Tc 9d").compare_to_dealer(&crate::hand!("Th 8d").stand(), &rules));
     }
-}
 
-impl IsTerminal for Hand<Split> {
-    fn is_terminal() -> bool {
-        false
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let hand: Hand<Stand> = crate::hand!("Ac 5d 9h").stand();
+        let json = serde_json::to_string(&hand).unwrap();
+        let restored: Hand<Stand> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(hand, restored);
+        assert_eq!(HandValue::Hard(15), restored.effective_value());
+        assert_eq!(HandStateKind::Stand, serde_json::from_str(&serde_json::to_string(&HandStateKind::Stand).unwrap()).unwrap());
+
+        // only the cards are trusted, and a hand cannot load into another state
+        assert!(!json.contains("total"));
+        let forged: Hand<Fresh> = serde_json::from_str(&serde_json::to_string(&crate::hand!("Tc 6d")).unwrap().replace("\"id\"", "\"total\":21,\"id\"")).unwrap();
+        assert_eq!((HandValue::Hard(16), false), (forged.effective_value(), forged.is_natural()));
+        assert!(serde_json::from_str::<Hand<Fresh>>(&json).is_err());
     }
-}
 
-impl IsTerminal for Hand<SplitAcesLocked> {
-    fn is_terminal() -> bool {
-        true
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_impossible_hands() {
+        use crate::hand::{Bust, DoubleDown, SplitAces, SplitAcesLocked, Surrender};
+
+        let record = |state: &str, cards: &str, origin: &str| {
+            let cards: Vec<String> = crate::cards!(cards).iter().map(|c| serde_json::to_string(c).unwrap()).collect();
+
+            format!(
+                r#"{{"state":"{state}","cards":[{}],"origin":"{origin}","id":{{"seat":0,"path":1}},"free_units":0}}"#,
+                cards.join(","),
+            )
+        };
+        let impossible = HandError::ImpossibleState.to_string();
+
+        let rejected = [
+            serde_json::from_str::<Hand<SplitAces>>(&record("SplitAces", "Td", "Split")).unwrap_err(),
+            serde_json::from_str::<Hand<SplitAces>>(&record("SplitAces", "Td 5c", "Split")).unwrap_err(),
+            serde_json::from_str::<Hand<SplitAcesLocked>>(&record("SplitAcesLocked", "Ad 5c", "Dealt")).unwrap_err(),
+            serde_json::from_str::<Hand<Bust>>(&record("Bust", "As Td", "Dealt")).unwrap_err(),
+            serde_json::from_str::<Hand<Stand>>(&record("Stand", "Ts Td 5c", "Dealt")).unwrap_err(),
+            serde_json::from_str::<Hand<Fresh>>(&record("Fresh", "8s", "Dealt")).unwrap_err(),
+            serde_json::from_str::<Hand<Fresh>>(&record("Fresh", "8s 3d", "Split")).unwrap_err(),
+            serde_json::from_str::<Hand<Split>>(&record("Split", "8s 3d", "Dealt")).unwrap_err(),
+            serde_json::from_str::<Hand<DoubleDown>>(&record("DoubleDown", "6s 5d", "Dealt")).unwrap_err(),
+            serde_json::from_str::<Hand<Surrender>>(&record("Surrender", "Ts 2d 3c", "Dealt")).unwrap_err(),
+        ];
+        for error in rejected {
+            assert!(error.to_string().starts_with(&impossible), "{error}");
+        }
+
+        // a double may bust on its one card, and split aces may have been hit where the rules allow it
+        assert!(serde_json::from_str::<Hand<DoubleDown>>(&record("DoubleDown", "Ts 2d Kh", "Dealt")).is_ok());
+        assert!(serde_json::from_str::<Hand<SplitAces>>(&record("SplitAces", "As 5d 2c", "Split")).is_ok());
     }
-}
 
-impl IsTerminal for Hand<Stand> {
-    fn is_terminal() -> bool {
-        true
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_hand_ids() {
+        let id: HandId = HandId::seat(4).left().right();
+        assert_eq!(id, serde_json::from_str(&serde_json::to_string(&id).unwrap()).unwrap());
+        assert_eq!(HandId::DEALER, serde_json::from_str(&serde_json::to_string(&HandId::DEALER).unwrap()).unwrap());
+
+        let error = serde_json::from_str::<HandId>(r#"{"seat":0,"path":0}"#).unwrap_err();
+        assert!(error.to_string().starts_with(&HandError::InvalidId.to_string()), "{error}");
     }
-}
 
-impl IsTerminal for Hand<Surrender> {
-    fn is_terminal() -> bool {
-        true
+    #[test]
+    fn split_hand_ids() {
+        let seat = HandId::seat(3);
+        let (left, right) = match crate::hand!("8c 8h").with_id(seat).split(Card::new(Suit::Spades, Rank::Eight), Card::new(Suit::Spades, Rank::Six)) {
+            SplitHands::Hands(left, right) => (left, right),
+            SplitHands::Aces(..) => panic!("eights are not aces"),
+        };
+        assert_eq!((seat.left(), seat.right()), (left.id(), right.id()));
+        assert_eq!(seat.left(), left.clone().stand().id());
+
+        let resplit = match left.split(Card::new(Suit::Diamonds, Rank::Two), Card::new(Suit::Diamonds, Rank::Three)) {
+            SplitHands::Hands(_, right) => right.id(),
+            SplitHands::Aces(..) => panic!("eights are not aces"),
+        };
+        assert_eq!((2, 3, Some(seat.left())), (resplit.splits(), resplit.seat_index(), resplit.parent()));
+        assert_eq!("seat 3LR", resplit.to_string());
+        assert_eq!("dealer", HandId::DEALER.to_string());
+        assert_eq!(None, seat.parent());
     }
-}
 
-pub struct Fresh;
-pub struct Bust;
-pub struct DoubleDown;
-pub struct Split;
-pub struct SplitAcesLocked;
-pub struct Stand;
-pub struct Surrender;
+    #[test]
+    fn inline_cards() {
+        let aces: Vec<Card> = vec![Card::new(Suit::Spades, Rank::Ace); 22];
+        let hand = Hand::new(aces.clone());
 
-pub trait HandState {}
-impl HandState for Fresh {}
-impl HandState for Bust {}
-impl HandState for DoubleDown {}
-impl HandState for Split {}
-impl HandState for SplitAcesLocked {}
-impl HandState for Stand {}
-impl HandState for Surrender {}
+        assert_eq!(&aces[..], hand.cards());
+        assert_eq!(HandValue::Hard(22), hand.effective_value());
+        assert_eq!(Hand::new(aces[..2].to_vec()), crate::hand!("As As"));
+    }
 
-pub trait IsTerminal {
-    fn is_terminal() -> bool;
-}
+    #[test]
+    fn compact_hands() {
+        for cards in ["8c 8d", "As 6h", "Td 6c 9s", "Ac Ad 9h", "Kh Qh"] {
+            let hand: Hand<Fresh> = cards.parse().unwrap();
+            let compact = CompactHand::from(&hand);
 
-pub trait HandInfo {
-    fn is_21(&self) -> bool;
+            assert_eq!(hand.value(), compact.value(), "{cards}");
+            assert_eq!(hand.is_pair(), compact.is_pair(), "{cards}");
+            assert_eq!(hand.is_bust(), compact.is_bust(), "{cards}");
+        }
 
-    fn is_natural(&self) -> bool;
+        let mut hand = CompactHand::new().with(Rank::Five).with(Rank::Five);
+        assert!(hand.is_pair());
+        hand.add(Rank::Ace);
+        assert_eq!((HandValue::Soft { lower: 11, upper: 21 }, 3, 2), (hand.value(), hand.cards(), hand.count(Rank::Five)));
+    }
 
-    fn is_bust(&self) -> bool;
+    #[test]
+    fn hands_from_str() {
+        let hand: Hand<Fresh> = "As Td".parse().unwrap();
+        assert!(hand.is_natural());
+        assert_eq!(hand, crate::hand!("As Td"));
+        assert_eq!(HandValue::Hard(16), crate::hand!("10c 6h").value());
 
-    fn is_pair(&self) -> bool;
+        assert_eq!(Err(CardError::ParseCardError), "  ".parse::<Hand<Fresh>>());
+        assert_eq!(Err(CardError::ParseSuitError), "As Tx".parse::<Hand<Fresh>>());
+        assert_eq!(22, "2c ".repeat(22).parse::<Hand<Fresh>>().unwrap().cards().len());
+        assert_eq!(Err(CardError::ParseCardError), "2c ".repeat(23).parse::<Hand<Fresh>>());
+    }
 
-    fn can_hit(&self, rules: RuleSet) -> bool;
+    #[test]
+    fn hand_predicates() {
+        let hand = |ranks: &[Rank]| Hand::new(ranks.iter().map(|&rank| Card::new(Suit::Clubs, rank)));
 
-    fn can_split(&self, rules: RuleSet) -> bool;
-    
-    fn can_surrender(&self, rules: RuleSet) -> bool;
+        assert!(hand(&[Rank::Eight, Rank::Eight]).is_pair_of(Rank::Eight));
+        assert!(!hand(&[Rank::Eight, Rank::Eight]).is_pair_of(Rank::Nine));
+        assert!(!hand(&[Rank::Eight, Rank::Eight, Rank::Eight]).is_pair());
 
-    fn can_double_down(&self, rules: RuleSet) -> bool;
-}
+        assert!(hand(&[Rank::Ace, Rank::Six]).is_soft());
+        assert!(hand(&[Rank::Ace, Rank::Six, Rank::Nine]).is_hard());
+        assert!(hand(&[Rank::Ten, Rank::Six]).is_hard());
 
-#[cfg(test)]
-mod tests {
-    use crate::{card::{Card, Rank, Suit}, hand::{Hand, HandValue}};
+        assert!(hand(&[Rank::Ace, Rank::King]).is_blackjack());
+        assert!(!hand(&[Rank::Seven, Rank::Seven, Rank::Seven]).is_blackjack());
+    }
 
     #[test]
     fn value_of_hands() {
@@ -249,5 +1374,422 @@ mod tests {
         );
 
         assert_eq!(HandValue::Soft { lower: 2, upper: 12 }, soft_hand2.value());
+
+        let collapsed = crate::hand!("Ac Td 5h");
+        assert_eq!(HandValue::Soft { lower: 16, upper: 26 }, collapsed.value());
+        assert_eq!(HandValue::Hard(16), collapsed.effective_value());
+        assert_eq!(HandValue::Soft { lower: 2, upper: 12 }, soft_hand2.effective_value());
+        assert_eq!(HandValue::Hard(16), CompactHand::from(&collapsed).effective_value());
+    }
+
+    #[test]
+    fn hand_transitions() {
+        let hand = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        );
+
+        let hand = match hand.hit(Card::new(Suit::Hearts, Rank::Two)) {
+            Hit::Live(hand) => hand,
+            Hit::Bust(_) => panic!("18 is not bust"),
+        };
+
+        // a hand that has hit can no longer surrender or split, and saying so hands it back instead of panicking
+        let rules = RuleSet::builder().build().unwrap();
+        let hand = hand.try_surrender(&rules, Card::new(Suit::Spades, Rank::Ten)).unwrap_err().into_hand();
+        let hand = hand.try_split(Card::new(Suit::Spades, Rank::Two), Card::new(Suit::Spades, Rank::Three), &rules, 1).unwrap_err().into_hand();
+
+        assert!(matches!(hand.hit(Card::new(Suit::Hearts, Rank::King)), Hit::Bust(_)));
+
+        let doubled = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Five),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        ).double_down(Card::new(Suit::Spades, Rank::Ten));
+
+        assert_eq!(3, doubled.cards().len());
+        assert!(doubled.is_21());
+
+        let aces = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Hearts, Rank::Ace),
+            ]
+        ).split(Card::new(Suit::Spades, Rank::Nine), Card::new(Suit::Spades, Rank::King));
+
+        match aces {
+            SplitHands::Aces(left, right) => {
+                assert_eq!(HandValue::Soft { lower: 10, upper: 20 }, left.value());
+                assert_eq!(HandValue::Soft { lower: 11, upper: 21 }, right.value());
+            },
+            SplitHands::Hands(..) => panic!("split aces must be kept apart"),
+        }
+    }
+
+    #[test]
+    fn rejected_actions_return_the_hand() {
+        let rules = RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .build()
+            .unwrap();
+
+        let hand = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        );
+
+        let rejected = hand.try_double_down(Card::new(Suit::Hearts, Rank::Two), &rules).unwrap_err();
+        assert_eq!(HandError::DoubleDownNotAllowed, rejected.error());
+
+        let rejected = rejected.into_hand().try_split(
+            Card::new(Suit::Hearts, Rank::Two),
+            Card::new(Suit::Hearts, Rank::Three),
+            &rules,
+            1,
+        ).unwrap_err();
+        assert_eq!(HandError::NotAPair, rejected.error());
+
+        let rejected = rejected.into_hand().try_surrender(&rules, Card::new(Suit::Spades, Rank::Ten)).unwrap_err();
+        assert_eq!(HandError::SurrenderNotAllowed, rejected.error());
+
+        let stood = rejected.into_hand().stand();
+        assert_eq!(HandValue::Hard(16), stood.value());
+    }
+
+    #[test]
+    fn split_aces_policy() {
+        let rules = |resplit: bool, hits_allowed: u8| RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .split_aces(SplitAcesPolicy::new(resplit, hits_allowed))
+            .build()
+            .unwrap();
+
+        let split = |first: Rank| match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Hearts, Rank::Ace),
+            ]
+        ).split(Card::new(Suit::Spades, first), Card::new(Suit::Spades, Rank::Two)) {
+            SplitHands::Aces(left, _) => left,
+            SplitHands::Hands(..) => panic!("split aces must be kept apart"),
+        };
+
+        let locked = split(Rank::Five).try_hit(Card::new(Suit::Diamonds, Rank::Two), &rules(false, 0));
+        assert_eq!(HandError::HitNotAllowed, locked.unwrap_err().error());
+
+        let one_hit = rules(false, 1);
+        let hand = match split(Rank::Five).try_hit(Card::new(Suit::Diamonds, Rank::Two), &one_hit).unwrap() {
+            Hit::Live(hand) => hand,
+            Hit::Bust(_) => panic!("soft 18 is not bust"),
+        };
+        assert_eq!(1, hand.hits_taken());
+
+        let second = hand.try_hit(Card::new(Suit::Diamonds, Rank::Two), &one_hit);
+        assert_eq!(HandError::HitNotAllowed, second.unwrap_err().error());
+
+        let aces = split(Rank::Ace);
+        let no_resplit = aces.clone().try_split(
+            Card::new(Suit::Diamonds, Rank::Two),
+            Card::new(Suit::Diamonds, Rank::Three),
+            &rules(false, 0),
+            2,
+        );
+        assert_eq!(HandError::ResplitNotAllowed, no_resplit.unwrap_err().error());
+
+        assert!(aces.try_split(
+            Card::new(Suit::Diamonds, Rank::Two),
+            Card::new(Suit::Diamonds, Rank::Three),
+            &rules(true, 0),
+            2,
+        ).is_ok());
+    }
+
+    #[test]
+    fn transition_table() {
+        assert!(Fresh::can_transition_to(HandStateKind::Surrender));
+        assert!(Split::can_transition_to(HandStateKind::Stand));
+        assert!(!Split::can_transition_to(HandStateKind::SplitAces));
+        assert!(!HandStateKind::SplitAces.can_transition_to(HandStateKind::DoubleDown));
+        assert!(!HandStateKind::Stand.can_transition_to(HandStateKind::Fresh));
+
+        let hand = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Two),
+                Card::new(Suit::Clubs, Rank::Three),
+            ]
+        ).hit(Card::new(Suit::Hearts, Rank::Four)) {
+            Hit::Live(hand) => hand,
+            Hit::Bust(_) => panic!("9 is not bust"),
+        };
+
+        // how many cards may be doubled on is left to the double rule
+        assert_eq!(HandStateKind::Fresh, hand.state());
+        assert!(hand.can_transition_to(HandStateKind::DoubleDown));
+        assert!(!hand.can_transition_to(HandStateKind::Surrender));
+        assert!(!hand.can_transition_to(HandStateKind::Bust));
+        assert!(hand.can_transition_to(HandStateKind::Stand));
+    }
+
+    #[test]
+    fn split_unequal_tens() {
+        let rules = |unequal_tens: bool| RuleSet::builder()
+            .split_rules(SplitRules::new(4).with_unequal_tens(unequal_tens))
+            .build()
+            .unwrap();
+
+        let king_ten = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::King),
+                Card::new(Suit::Hearts, Rank::Ten),
+            ]
+        );
+        assert!(!king_ten.is_pair());
+        assert!(!king_ten.can_split(&rules(false), 1));
+        assert!(king_ten.can_split(&rules(true), 1));
+
+        let rejected = king_ten.try_split(Card::new(Suit::Spades, Rank::Two), Card::new(Suit::Spades, Rank::Three), &rules(false), 1);
+        assert_eq!(HandError::NotAPair, rejected.unwrap_err().error());
+
+        let nine_ten = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Nine),
+                Card::new(Suit::Hearts, Rank::Ten),
+            ]
+        );
+        assert!(!nine_ten.can_split(&rules(true), 1));
+    }
+
+    #[test]
+    fn double_rules() {
+        let rules = |double_rule: DoubleRule| RuleSet::builder()
+            .double_rule(double_rule)
+            .build()
+            .unwrap();
+
+        let soft_17 = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        );
+        assert!(soft_17.can_double_down(&rules(DoubleRule::any_two_cards())));
+        assert!(!soft_17.can_double_down(&rules(DoubleRule::hard_only((3..=20).collect()))));
+
+        // hard 9-11 but soft 13-18 only
+        let mixed = DoubleRule::new(vec![9, 10, 11], (13..=18).collect(), 2);
+        assert!(soft_17.can_double_down(&rules(mixed)));
+        let soft_19 = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Clubs, Rank::Eight),
+            ]
+        );
+        assert!(!soft_19.can_double_down(&rules(mixed)));
+        let hard_12 = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Two),
+            ]
+        );
+        assert!(!hard_12.can_double_down(&rules(mixed)));
+
+        let nine = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Two),
+                Card::new(Suit::Clubs, Rank::Three),
+            ]
+        ).hit(Card::new(Suit::Hearts, Rank::Four)) {
+            Hit::Live(hand) => hand,
+            Hit::Bust(_) => panic!("9 is not bust"),
+        };
+        assert!(!nine.can_double_down(&rules(DoubleRule::hard_only(vec![9, 10, 11]))));
+        assert!(nine.can_double_down(&rules(DoubleRule::new(vec![9, 10, 11], vec![], 3))));
+
+        assert!(RuleSet::builder().double_rule(DoubleRule::new(vec![9, 10, 11], vec![], 1)).build().is_err());
+    }
+
+    #[test]
+    fn hand_info_follows_state_and_rules() {
+        let rules = |resplit: bool, hits_allowed: u8, das: bool| RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .split_aces(SplitAcesPolicy::new(resplit, hits_allowed))
+            .das(if das { DoubleAfterSplit::Any } else { DoubleAfterSplit::None })
+            .surrender(SurrenderRule::Late)
+            .build()
+            .unwrap();
+
+        let stood = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        ).stand();
+        assert!(!stood.can_hit(&rules(false, 0, true)));
+        assert!(!stood.can_surrender(&rules(false, 0, true), Card::new(Suit::Spades, Rank::Ten)));
+
+        let (aces, _) = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Hearts, Rank::Ace),
+            ]
+        ).split(Card::new(Suit::Spades, Rank::Ace), Card::new(Suit::Spades, Rank::Nine)) {
+            SplitHands::Aces(left, right) => (left, right),
+            SplitHands::Hands(..) => panic!("split aces must be kept apart"),
+        };
+        assert!(!aces.can_hit(&rules(true, 0, true)));
+        assert!(aces.can_hit(&rules(true, 1, true)));
+        assert!(!aces.can_split(&rules(false, 1, true), 2));
+        assert!(aces.can_split(&rules(true, 0, true), 2));
+        assert!(!aces.can_double_down(&rules(true, 1, true)));
+
+        let (fives, _) = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Five),
+                Card::new(Suit::Hearts, Rank::Five),
+            ]
+        ).split(Card::new(Suit::Spades, Rank::Six), Card::new(Suit::Spades, Rank::Two)) {
+            SplitHands::Hands(left, right) => (left, right),
+            SplitHands::Aces(..) => panic!("fives are not aces"),
+        };
+        assert!(fives.can_double_down(&rules(false, 0, true)));
+        assert!(!fives.can_double_down(&rules(false, 0, false)));
+
+        // the left hand is 5-6, a hard 11
+        let das_on = |totals: Vec<u64>| RuleSet::builder().das(DoubleAfterSplit::Totals(totals.into())).build().unwrap();
+        assert!(!fives.can_double_down(&das_on(vec![10])));
+        assert!(fives.can_double_down(&das_on(vec![10, 11])));
+        assert!(!fives.can_surrender(&rules(false, 0, true), Card::new(Suit::Spades, Rank::Ten)));
+    }
+
+    #[test]
+    fn per_rank_split_limits() {
+        let rules = RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .split_rules(SplitRules::new(4).with_max_hands(Rank::Ace, 2))
+            .split_aces(SplitAcesPolicy::new(true, 0))
+            .surrender(SurrenderRule::Late)
+            .build()
+            .unwrap();
+
+        let eights = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Eight),
+                Card::new(Suit::Hearts, Rank::Eight),
+            ]
+        );
+        assert!(eights.can_split(&rules, 3));
+        assert!(!eights.can_split(&rules, 4));
+
+        let rejected = eights.try_split(
+            Card::new(Suit::Spades, Rank::Two),
+            Card::new(Suit::Spades, Rank::Three),
+            &rules,
+            4,
+        ).unwrap_err();
+        assert_eq!(HandError::MaxHandsReached, rejected.error());
+
+        let aces = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Hearts, Rank::Ace),
+            ]
+        );
+        assert!(aces.can_split(&rules, 1));
+        assert!(!aces.can_split(&rules, 2));
+    }
+
+    #[test]
+    fn split_refusal_reasons() {
+        let rules = RuleSet::builder()
+            .split_rules(SplitRules::new(4))
+            .split_aces(SplitAcesPolicy::new(false, 0))
+            .build()
+            .unwrap();
+
+        let eights = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Eight),
+                Card::new(Suit::Hearts, Rank::Eight),
+            ]
+        );
+        assert_eq!(None, eights.split_refusal(&rules, 3));
+        assert_eq!(Some(HandError::MaxHandsReached), eights.split_refusal(&rules, 4));
+        assert_eq!(Some(HandError::SplitNotAllowed), eights.stand().split_refusal(&rules, 1));
+
+        let seventeen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Hearts, Rank::Seven),
+            ]
+        );
+        assert_eq!(Some(HandError::NotAPair), seventeen.split_refusal(&rules, 1));
+
+        let aces = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Hearts, Rank::Ace),
+            ]
+        ).split(Card::new(Suit::Spades, Rank::Ace), Card::new(Suit::Spades, Rank::Two)) {
+            SplitHands::Aces(left, _) => left,
+            SplitHands::Hands(..) => panic!("split aces must be kept apart"),
+        };
+        assert_eq!(Some(HandError::ResplitNotAllowed), aces.split_refusal(&rules, 2));
+
+        let rejected = aces.try_split(
+            Card::new(Suit::Spades, Rank::Three),
+            Card::new(Suit::Spades, Rank::Four),
+            &rules,
+            2,
+        ).unwrap_err();
+        assert_eq!(HandError::ResplitNotAllowed, rejected.error());
+    }
+
+    #[test]
+    fn surrender_restrictions() {
+        let rules = |after_split: bool, surrender: SurrenderRule| RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .surrender(surrender)
+            .surrender_policy(SurrenderPolicy::new(after_split))
+            .build()
+            .unwrap();
+
+        let ten = Card::new(Suit::Spades, Rank::Ten);
+        let ace = Card::new(Suit::Spades, Rank::Ace);
+
+        let sixteen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Six),
+            ]
+        );
+        assert!(sixteen.can_surrender(&rules(false, SurrenderRule::Late), ace));
+        assert!(!sixteen.can_surrender(&rules(false, SurrenderRule::LateNotVsAce), ace));
+        assert!(sixteen.can_surrender(&rules(false, SurrenderRule::LateNotVsAce), ten));
+        assert!(!sixteen.can_surrender(&rules(false, SurrenderRule::None), ten));
+
+        let hit = match sixteen.clone().hit(Card::new(Suit::Hearts, Rank::Two)) {
+            Hit::Live(hand) => hand,
+            Hit::Bust(_) => panic!("18 is not bust"),
+        };
+        assert!(!hit.can_surrender(&rules(true, SurrenderRule::Late), ten));
+
+        let (eights, _) = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Eight),
+                Card::new(Suit::Hearts, Rank::Eight),
+            ]
+        ).split(Card::new(Suit::Spades, Rank::Eight), Card::new(Suit::Spades, Rank::Two)) {
+            SplitHands::Hands(left, right) => (left, right),
+            SplitHands::Aces(..) => panic!("eights are not aces"),
+        };
+        assert!(!eights.can_surrender(&rules(false, SurrenderRule::Late), ten));
+        assert!(eights.can_surrender(&rules(true, SurrenderRule::Late), ten));
+        assert!(!eights.can_surrender(&rules(true, SurrenderRule::Early), ten));
+        assert!(sixteen.can_surrender(&rules(false, SurrenderRule::Early), ten));
     }
 }
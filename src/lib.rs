@@ -1,6 +1,17 @@
 pub mod card;
 pub mod deck;
+pub mod composition;
+pub mod count;
+pub mod shuffle;
+pub mod sequencing;
 pub mod hand;
+pub mod dealer;
 pub mod rule;
 pub mod play;
+pub mod encoding;
+pub mod strategy;
 pub mod game;
+pub mod paytable;
+#[cfg(feature = "side-bets")]
+pub mod side;
+pub mod stats;
@@ -0,0 +1,125 @@
+use std::{collections::BTreeMap, error::Error, fmt};
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+// net result per unit wagered for each way a bet can resolve; anything missing is a loss
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(
+    try_from = "BTreeMap<K, f64>",
+    into = "BTreeMap<K, f64>",
+    bound(serialize = "K: Ord + Clone + Serialize", deserialize = "K: Ord + DeserializeOwned"),
+))]
+pub struct Paytable<K: Ord> {
+    entries: BTreeMap<K, f64>,
+}
+
+impl<K: Ord> Paytable<K> {
+    pub fn new<I>(entries: I) -> Result<Self, PaytableError>
+        where I: IntoIterator<Item = (K, f64)>
+    {
+        let entries: BTreeMap<K, f64> = entries.into_iter().collect();
+
+        if entries.is_empty() {
+            return Err(PaytableError::Empty);
+        }
+
+        // a bet can at most be lost in full
+        if entries.values().any(|&m| !m.is_finite() || m < -1.0) {
+            return Err(PaytableError::InvalidMultiplier);
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn multiplier(&self, key: &K) -> Option<f64> {
+        self.entries.get(key).copied()
+    }
+
+    pub fn pays(&self, key: &K) -> f64 {
+        self.multiplier(key).unwrap_or(-1.0)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&K, f64)> {
+        self.entries.iter().map(|(k, &m)| (k, m))
+    }
+
+    // expected net result per unit given the probability of each key
+    pub fn expected_value<'a, I>(&self, probabilities: I) -> f64
+        where I: IntoIterator<Item = (&'a K, f64)>, K: 'a
+    {
+        probabilities
+            .into_iter()
+            .map(|(k, p)| p * self.pays(k))
+            .sum()
+    }
+}
+
+impl<K: Ord> TryFrom<BTreeMap<K, f64>> for Paytable<K> {
+    type Error = PaytableError;
+
+    fn try_from(entries: BTreeMap<K, f64>) -> Result<Self, Self::Error> {
+        Self::new(entries)
+    }
+}
+
+impl<K: Ord> From<Paytable<K>> for BTreeMap<K, f64> {
+    fn from(paytable: Paytable<K>) -> Self {
+        paytable.entries
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum PaytableError {
+    Empty,
+    InvalidMultiplier,
+}
+
+impl fmt::Display for PaytableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "paytable must have at least one entry"),
+            Self::InvalidMultiplier => write!(f, "paytable multipliers must be finite and at least -1"),
+        }
+    }
+}
+
+impl Error for PaytableError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::Outcome, paytable::{Paytable, PaytableError}, rule::{BlackjackPayout, RuleSet}};
+
+    #[test]
+    fn create_paytables() {
+        assert_eq!(Err(PaytableError::Empty), Paytable::<u8>::new(vec![]));
+        assert_eq!(Err(PaytableError::InvalidMultiplier), Paytable::new(vec![(1, -2.0)]));
+        assert_eq!(Err(PaytableError::InvalidMultiplier), Paytable::new(vec![(1, f64::NAN)]));
+
+        // perfect pairs style side bet keyed by a label
+        let pairs = Paytable::new(vec![("mixed", 5.0), ("colored", 12.0), ("perfect", 25.0)]).unwrap();
+        assert_eq!(Some(12.0), pairs.multiplier(&"colored"));
+        assert_eq!(-1.0, pairs.pays(&"none"));
+        assert!((pairs.expected_value(vec![(&"perfect", 0.02), (&"none", 0.98)]) + 0.48).abs() < 1e-9);
+    }
+
+    #[test]
+    fn outcome_paytable() {
+        let rules = RuleSet::builder().blackjack_payout(BlackjackPayout::SixToFive).build().unwrap();
+        let paytable = Outcome::paytable(&rules);
+
+        assert_eq!(1.2, paytable.pays(&Outcome::Blackjack));
+        assert_eq!(-0.5, paytable.pays(&Outcome::Surrender));
+        assert_eq!(5, paytable.entries().count());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn paytable_serde() {
+        let paytable = Outcome::paytable(&RuleSet::builder().build().unwrap());
+        let json = serde_json::to_string(&paytable).unwrap();
+
+        assert_eq!(paytable, serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<Paytable<Outcome>>(r#"{"Win":-3.0}"#).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use std::{error::Error, fmt, str::FromStr};
+use crate::{card::Card, encoding::Encoding, hand::{Hand, HandInfo, HandState, HandStateKind}, rule::RuleSet};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Action {
+    Hit,
+    Stand,
+    DoubleDown,
+    Split,
+    Surrender,
+    Insurance,
+    #[cfg(feature = "variant-switch")]
+    Switch,
+}
+
+impl Action {
+    // the one-letter code used by every text format
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Hit => "H",
+            Self::Stand => "S",
+            Self::DoubleDown => "D",
+            Self::Split => "P",
+            Self::Surrender => "R",
+            Self::Insurance => "I",
+            #[cfg(feature = "variant-switch")]
+            Self::Switch => "X",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Action {
+    type Err = CodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "H" => Ok(Self::Hit),
+            "S" => Ok(Self::Stand),
+            "D" => Ok(Self::DoubleDown),
+            "P" => Ok(Self::Split),
+            "R" => Ok(Self::Surrender),
+            "I" => Ok(Self::Insurance),
+            #[cfg(feature = "variant-switch")]
+            "X" => Ok(Self::Switch),
+            _ => Err(CodeError::UnknownAction),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum CodeError {
+    UnknownAction,
+    UnknownOutcome,
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownAction => write!(f, "action code must be one of H, S, D, P, R, I, X"),
+            Self::UnknownOutcome => write!(f, "outcome code must be one of BJ, W, P, L, R"),
+        }
+    }
+}
+
+impl Error for CodeError {}
+
+// what the table knows about a decision beyond the hand itself
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct Context {
+    upcard: Card,
+    hole_card: Option<Card>,
+    hands: u64,
+}
+
+impl Context {
+    pub fn new(upcard: Card, hands: u64) -> Self {
+        Self { upcard, hole_card: None, hands }
+    }
+
+    // only known to the player in double exposure games
+    pub fn with_hole_card(self, hole_card: Card) -> Self {
+        Self { hole_card: Some(hole_card), ..self }
+    }
+
+    pub fn upcard(&self) -> Card {
+        self.upcard
+    }
+
+    pub fn hole_card(&self) -> Option<Card> {
+        self.hole_card
+    }
+
+    pub fn hands(&self) -> u64 {
+        self.hands
+    }
+}
+
+pub fn legal_actions<S>(hand: &Hand<S>, rules: &RuleSet, context: &Context) -> Vec<Action>
+    where S: HandState
+{
+    let mut actions: Vec<Action> = Vec::new();
+
+    if hand.can_hit(rules) {
+        actions.push(Action::Hit);
+    }
+
+    if hand.can_transition_to(HandStateKind::Stand) || hand.can_transition_to(HandStateKind::SplitAcesLocked) {
+        actions.push(Action::Stand);
+    }
+
+    if hand.can_double_down(rules) {
+        actions.push(Action::DoubleDown);
+    }
+
+    if hand.can_split(rules, context.hands()) {
+        actions.push(Action::Split);
+    }
+
+    if hand.can_surrender(rules, context.upcard()) {
+        actions.push(Action::Surrender);
+    }
+
+    // pairing the two hands up is left to the caller
+    #[cfg(feature = "variant-switch")]
+    if rules.switch() && hand.state() == HandStateKind::Fresh && hand.is_fresh() {
+        actions.push(Action::Switch);
+    }
+
+    actions
+}
+
+// picks an action from an encoded observation; external models plug in through a closure
+pub trait Strategy {
+    fn decide(&mut self, observation: &[f32], legal: &[Action]) -> Action;
+}
+
+impl<F> Strategy for F
+    where F: FnMut(&[f32], &[Action]) -> Action
+{
+    fn decide(&mut self, observation: &[f32], legal: &[Action]) -> Action {
+        self(observation, legal)
+    }
+}
+
+// what a checked strategy plays in place of an illegal answer
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum Substitute {
+    Stand,
+    FirstLegal,
+}
+
+impl Substitute {
+    pub fn pick(&self, legal: &[Action]) -> Action {
+        match self {
+            Self::Stand => Action::Stand,
+            Self::FirstLegal => legal.first().copied().unwrap_or(Action::Stand),
+        }
+    }
+}
+
+// everything known about an illegal answer, handed to the hook before the substitute is played
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+pub struct IllegalAction {
+    action: Action,
+    substitute: Action,
+    legal: Vec<Action>,
+    observation: Vec<f32>,
+}
+
+impl IllegalAction {
+    pub fn action(&self) -> Action {
+        self.action
+    }
+
+    pub fn substitute(&self) -> Action {
+        self.substitute
+    }
+
+    pub fn legal(&self) -> &[Action] {
+        &self.legal
+    }
+
+    pub fn observation(&self) -> &[f32] {
+        &self.observation
+    }
+}
+
+pub trait IllegalActionHook {
+    fn illegal(&mut self, record: &IllegalAction);
+}
+
+impl<F> IllegalActionHook for F
+    where F: FnMut(&IllegalAction)
+{
+    fn illegal(&mut self, record: &IllegalAction) {
+        self(record)
+    }
+}
+
+// wraps a strategy so illegal answers are reported and replaced instead of silently played
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub struct Checked<T, H> {
+    strategy: T,
+    hook: H,
+    substitute: Substitute,
+}
+
+impl<T, H> Checked<T, H>
+    where T: Strategy, H: IllegalActionHook
+{
+    pub fn new(strategy: T, hook: H, substitute: Substitute) -> Self {
+        Self { strategy, hook, substitute }
+    }
+
+    pub fn into_inner(self) -> (T, H) {
+        (self.strategy, self.hook)
+    }
+}
+
+impl<T, H> Strategy for Checked<T, H>
+    where T: Strategy, H: IllegalActionHook
+{
+    fn decide(&mut self, observation: &[f32], legal: &[Action]) -> Action {
+        let action: Action = self.strategy.decide(observation, legal);
+
+        if legal.contains(&action) {
+            return action;
+        }
+
+        let substitute: Action = self.substitute.pick(legal);
+        self.hook.illegal(&IllegalAction {
+            action,
+            substitute,
+            legal: legal.to_vec(),
+            observation: observation.to_vec(),
+        });
+
+        substitute
+    }
+}
+
+// encodes the decision and hands it to the strategy; wrap it in `Checked` to catch illegal answers
+pub fn decide<S, T>(
+    strategy: &mut T,
+    encoding: Encoding,
+    hand: &Hand<S>,
+    rules: &RuleSet,
+    context: &Context,
+    true_count: f64,
+    penetration: f64,
+) -> Action
+    where S: HandState, T: Strategy
+{
+    let observation: Vec<f32> = encoding.encode(hand, context, true_count, penetration);
+
+    strategy.decide(&observation, &legal_actions(hand, rules, context))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, hand::{Hand, Hit, SplitHands}, encoding::Encoding, play::{decide, legal_actions, Action, Checked, CodeError, Context, IllegalAction, Substitute}, rule::{DoubleRule, RuleSet, SurrenderRule}};
+
+    #[test]
+    fn legal_actions_in_context() {
+        let rules = RuleSet::builder()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .surrender(SurrenderRule::LateNotVsAce)
+            .build()
+            .unwrap();
+
+        let ten = Card::new(Suit::Spades, Rank::Ten);
+        let ace = Card::new(Suit::Spades, Rank::Ace);
+
+        let nines = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Nine),
+                Card::new(Suit::Hearts, Rank::Nine),
+            ]
+        );
+        assert_eq!(
+            vec![Action::Hit, Action::Stand, Action::Split, Action::Surrender],
+            legal_actions(&nines, &rules, &Context::new(ten, 1)),
+        );
+        assert_eq!(
+            vec![Action::Hit, Action::Stand],
+            legal_actions(&nines, &rules, &Context::new(ace, 4)),
+        );
+
+        let hit = match nines.hit(Card::new(Suit::Hearts, Rank::Two)) {
+            Hit::Live(hand) => hand,
+            Hit::Bust(_) => panic!("20 is not bust"),
+        };
+        assert_eq!(vec![Action::Hit, Action::Stand], legal_actions(&hit, &rules, &Context::new(ten, 1)));
+
+        let (aces, _) = match Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ace),
+                Card::new(Suit::Hearts, Rank::Ace),
+            ]
+        ).split(Card::new(Suit::Spades, Rank::Five), Card::new(Suit::Spades, Rank::Two)) {
+            SplitHands::Aces(left, right) => (left, right),
+            SplitHands::Hands(..) => panic!("split aces must be kept apart"),
+        };
+        assert_eq!(vec![Action::Stand], legal_actions(&aces, &rules, &Context::new(ten, 2)));
+
+        assert!(legal_actions(&aces.stand(), &rules, &Context::new(ten, 2)).is_empty());
+    }
+
+    #[test]
+    fn action_codes() {
+        let actions = [Action::Hit, Action::Stand, Action::DoubleDown, Action::Split, Action::Surrender, Action::Insurance, #[cfg(feature = "variant-switch")] Action::Switch];
+        let codes: String = actions.iter().map(|a| a.to_string()).collect();
+
+        assert_eq!(if cfg!(feature = "variant-switch") { "HSDPRIX" } else { "HSDPRI" }, codes);
+        for action in actions {
+            assert_eq!(Ok(action), action.code().parse());
+        }
+
+        assert_eq!(Err(CodeError::UnknownAction), "h".parse::<Action>());
+    }
+
+    #[test]
+    fn closure_strategy() {
+        let rules = RuleSet::builder().build().unwrap();
+        let context = Context::new(Card::new(Suit::Spades, Rank::Six), 1);
+
+        // doubles whenever the total is 11 and it is allowed, otherwise stands
+        let mut model = |observation: &[f32], legal: &[Action]| {
+            if observation[0] == 11.0 / 21.0 && legal.contains(&Action::DoubleDown) {
+                Action::DoubleDown
+            } else {
+                Action::Stand
+            }
+        };
+
+        let eleven = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Six),
+                Card::new(Suit::Clubs, Rank::Five),
+            ]
+        );
+        assert_eq!(Action::DoubleDown, decide(&mut model, Encoding::V1, &eleven, &rules, &context, 0.0, 0.0));
+
+        let twelve = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Seven),
+                Card::new(Suit::Clubs, Rank::Five),
+            ]
+        );
+        assert_eq!(Action::Stand, decide(&mut model, Encoding::V1, &twelve, &rules, &context, 0.0, 0.0));
+    }
+
+    #[test]
+    fn checked_strategy_reports_illegal_actions() {
+        let rules = RuleSet::builder().build().unwrap();
+        let context = Context::new(Card::new(Suit::Spades, Rank::Six), 1);
+
+        let seventeen = Hand::new(
+            vec![
+                Card::new(Suit::Clubs, Rank::Ten),
+                Card::new(Suit::Clubs, Rank::Seven),
+            ]
+        );
+
+        let mut log: Vec<IllegalAction> = Vec::new();
+        let mut checked = Checked::new(
+            |_: &[f32], _: &[Action]| Action::Split,
+            |record: &IllegalAction| log.push(record.clone()),
+            Substitute::FirstLegal,
+        );
+
+        assert_eq!(Action::Hit, decide(&mut checked, Encoding::V1, &seventeen, &rules, &context, 0.0, 0.0));
+
+        assert_eq!(1, log.len());
+        assert_eq!(Action::Split, log[0].action());
+        assert_eq!(Action::Hit, log[0].substitute());
+        assert!(!log[0].legal().contains(&Action::Split));
+    }
+}
@@ -1,18 +1,422 @@
 use std::{error::Error, fmt};
+use crate::{card::Rank, deck::SINGLE_DECK_SIZE, hand::{HandId, HandValue}, stats::NaturalFrequencies};
+#[cfg(feature = "variant-spanish21")]
+use crate::{deck::SPANISH_DECK_SIZE, paytable::Paytable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::{fs, io, path::Path};
+
+// no house pays more than 3:1 on a natural, so anything above is a typo
+const MAX_BLACKJACK_PAYOUT: f64 = 3.0;
+
+// a round can take about three cards for every player and for the dealer
+const CARDS_PER_SEAT: usize = 3;
+
+// cards in one deck, with the tens stripped out for Spanish 21
+fn deck_size(#[cfg(feature = "variant-spanish21")] spanish_21: bool) -> usize {
+    #[cfg(feature = "variant-spanish21")]
+    if spanish_21 {
+        return SPANISH_DECK_SIZE;
+    }
+    SINGLE_DECK_SIZE
+}
+
+// shuffle with a quarter of the shoe left unless told otherwise
+fn default_shuffle_kind(decks: usize, deck_size: usize) -> ShuffleKind {
+    ShuffleKind::Threshold((decks * deck_size / 4) as u64)
+}
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DealerOnSoft17 {
     H17,
     S17,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ShuffleKind {
     Continuous,
     Threshold(u64),
 }
 
+// what happens when the shoe runs dry mid-round
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ShoeExhaustion {
+    Reshuffle,
+    Void,
+    // fresh decks finish the round and the old cards are retired with it
+    NewShoe,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BlackjackPayout {
+    ThreeToTwo,
+    SixToFive,
+    TwoToOne,
+    Custom(f64),
+}
+
+impl BlackjackPayout {
+    // net win per unit wagered on a natural
+    pub fn ratio(&self) -> f64 {
+        match self {
+            Self::ThreeToTwo => 1.5,
+            Self::SixToFive => 1.2,
+            Self::TwoToOne => 2.0,
+            Self::Custom(ratio) => *ratio,
+        }
+    }
+}
+
+// hand totals as a bitmask, bit n for total n; config files still list the totals
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "Vec<u64>", into = "Vec<u64>"))]
+pub struct TotalSet {
+    mask: u32,
+}
+
+impl TotalSet {
+    pub fn contains(&self, total: u64) -> bool {
+        total < 32 && self.mask & (1 << total) != 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..32).filter(|&v| self.contains(v))
+    }
+
+    pub fn to_vec(&self) -> Vec<u64> {
+        self.iter().collect()
+    }
+}
+
+// runs of three or more totals as ranges, e.g. "9-11" or "10/11"
+impl fmt::Display for TotalSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut runs: Vec<(u64, u64)> = Vec::new();
+        for v in self.iter() {
+            match runs.last_mut() {
+                Some((_, end)) if *end + 1 == v => *end = v,
+                _ => runs.push((v, v)),
+            }
+        }
+
+        let runs: Vec<String> = runs.iter()
+            .map(|&(start, end)| match end - start {
+                0 => start.to_string(),
+                1 => format!("{}/{}", start, end),
+                _ => format!("{}-{}", start, end),
+            })
+            .collect();
+        write!(f, "{}", runs.join("/"))
+    }
+}
+
+impl FromIterator<u64> for TotalSet {
+    // totals past the mask land on bit 31 so validation still sees them
+    fn from_iter<I: IntoIterator<Item = u64>>(totals: I) -> Self {
+        Self { mask: totals.into_iter().fold(0, |mask, v| mask | 1 << v.min(31)) }
+    }
+}
+
+impl From<Vec<u64>> for TotalSet {
+    fn from(totals: Vec<u64>) -> Self {
+        totals.into_iter().collect()
+    }
+}
+
+impl From<TotalSet> for Vec<u64> {
+    fn from(totals: TotalSet) -> Self {
+        totals.to_vec()
+    }
+}
+
+// which hard and soft totals may be doubled, and how many cards the hand may hold
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DoubleRule {
+    hard: TotalSet,
+    soft: TotalSet,
+    max_cards: u8,
+}
+
+impl DoubleRule {
+    pub fn new(hard: Vec<u64>, soft: Vec<u64>, max_cards: u8) -> Self {
+        Self { hard: hard.into(), soft: soft.into(), max_cards }
+    }
+
+    pub fn any_two_cards() -> Self {
+        Self::new((3..=20).collect(), (12..=20).collect(), 2)
+    }
+
+    pub fn hard_only(totals: Vec<u64>) -> Self {
+        Self::new(totals, Vec::new(), 2)
+    }
+
+    pub fn hard(&self) -> TotalSet {
+        self.hard
+    }
+
+    pub fn soft(&self) -> TotalSet {
+        self.soft
+    }
+
+    pub fn max_cards(&self) -> u8 {
+        self.max_cards
+    }
+
+    // soft hands are judged by their upper total, once an ace that must count as one has made them hard
+    pub fn allows(&self, value: HandValue, cards: usize) -> bool {
+        if cards > self.max_cards as usize {
+            return false;
+        }
+
+        match value.effective() {
+            HandValue::Hard(v) => self.hard.contains(v),
+            HandValue::Soft { upper, .. } => self.soft.contains(upper),
+        }
+    }
+}
+
+// which hands grown from a split may be doubled; split aces never are
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DoubleAfterSplit {
+    None,
+    Any,
+    // soft hands are judged by their upper total
+    Totals(TotalSet),
+}
+
+impl DoubleAfterSplit {
+    pub fn allows(&self, value: HandValue) -> bool {
+        match self {
+            Self::None => false,
+            Self::Any => true,
+            Self::Totals(totals) => match value.effective() {
+                HandValue::Hard(v) => totals.contains(v),
+                HandValue::Soft { upper, .. } => totals.contains(upper),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SplitRules {
+    max_hands: [u64; 13],
+    #[cfg_attr(feature = "serde", serde(default))]
+    unequal_tens: bool,
+}
+
+impl SplitRules {
+    pub fn new(max_hands: u64) -> Self {
+        Self { max_hands: [max_hands; 13], unequal_tens: false }
+    }
+
+    // lets any two ten-value cards be split, not just a same-rank pair
+    pub fn with_unequal_tens(self, unequal_tens: bool) -> Self {
+        Self { unequal_tens, ..self }
+    }
+
+    pub fn unequal_tens(&self) -> bool {
+        self.unequal_tens
+    }
+
+    pub fn with_max_hands(self, rank: Rank, max_hands: u64) -> Self {
+        let mut rules = self;
+        rules.max_hands[rank as usize] = max_hands;
+
+        rules
+    }
+
+    pub fn max_hands(&self, rank: Rank) -> u64 {
+        self.max_hands[rank as usize]
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SplitAcesPolicy {
+    resplit: bool,
+    hits_allowed: u8,
+}
+
+impl SplitAcesPolicy {
+    pub fn new(resplit: bool, hits_allowed: u8) -> Self {
+        Self { resplit, hits_allowed }
+    }
+
+    pub fn resplit(&self) -> bool {
+        self.resplit
+    }
+
+    pub fn hits_allowed(&self) -> u8 {
+        self.hits_allowed
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SurrenderRule {
+    None,
+    Late,
+    Early,
+    EarlyExceptAce,
+    LateNotVsAce,
+}
+
+impl SurrenderRule {
+    pub fn allows(&self, upcard: Rank) -> bool {
+        match self {
+            Self::None => false,
+            Self::Late | Self::Early => true,
+            Self::EarlyExceptAce | Self::LateNotVsAce => upcard != Rank::Ace,
+        }
+    }
+
+    // early surrender is offered before the dealer checks for blackjack
+    pub fn is_early(&self) -> bool {
+        matches!(self, Self::Early | Self::EarlyExceptAce)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SurrenderPolicy {
+    after_split: bool,
+}
+
+impl SurrenderPolicy {
+    pub fn new(after_split: bool) -> Self {
+        Self { after_split }
+    }
+
+    pub fn after_split(&self) -> bool {
+        self.after_split
+    }
+}
+
+// promotional hands paid at their own multiplier instead of the usual payout
+#[cfg(feature = "variant-spanish21")]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Bonus {
+    SuitedBlackjack,
+    SixSevenEight,
+    SixSevenEightSuited,
+    SixSevenEightSpades,
+    SevenSevenSeven,
+    SevenSevenSevenSuited,
+    SevenSevenSevenSpades,
+}
+
+#[cfg(feature = "variant-spanish21")]
+pub type BonusPayouts = Paytable<Bonus>;
+
+// charged every round on top of the wager
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RoundFee {
+    Flat(f64),
+    Commission(f64),
+}
+
+impl RoundFee {
+    pub fn amount(&self, bet: f64) -> f64 {
+        match self {
+            Self::Flat(fee) => *fee,
+            Self::Commission(rate) => bet * rate,
+        }
+    }
+
+    // the fee as a fraction of the bet, for folding into per-unit EV
+    pub fn in_units(&self, bet: f64) -> f64 {
+        self.amount(bet) / bet
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::Flat(fee) => fee.is_finite() && *fee >= 0.0,
+            Self::Commission(rate) => (0.0..1.0).contains(rate),
+        }
+    }
+}
+
+// what happens to a wager outside the table limits
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BetLimitPolicy {
+    Reject,
+    Clamp,
+}
+
+// the rules `RuleSet::edge_delta` has published coefficients for
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RuleDifference {
+    Decks,
+    DealerOnSoft17,
+    BlackjackPayout,
+    Das,
+    Surrender,
+    ResplitAces,
+}
+
+// what a player loses to a dealer natural in a no-hole-card game, once doubles and splits are on the table
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EnhcLoss {
+    AllBets,
+    OriginalBetsOnly,
+    BustedBetsPlusOne,
+}
+
+// who takes a standing tie between player and dealer
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TieRule {
+    Push,
+    DealerWins,
+    DealerWinsExceptNaturals,
+}
+
+// regional tie-break oddities applied when hands are compared
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SettlementPolicy {
+    player_21_wins: bool,
+    split_21_pushes_natural: bool,
+    five_card_wins_ties: bool,
+}
+
+impl SettlementPolicy {
+    pub fn new(player_21_wins: bool, split_21_pushes_natural: bool, five_card_wins_ties: bool) -> Self {
+        Self { player_21_wins, split_21_pushes_natural, five_card_wins_ties }
+    }
+
+    // any player 21 beats a dealer 21 that is not a natural
+    pub fn player_21_wins(&self) -> bool {
+        self.player_21_wins
+    }
+
+    // a two-card 21 after splitting pushes a dealer natural instead of losing
+    pub fn split_21_pushes_natural(&self) -> bool {
+        self.split_21_pushes_natural
+    }
+
+    pub fn five_card_wins_ties(&self) -> bool {
+        self.five_card_wins_ties
+    }
+}
+
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RuleSetBuilder", into = "RuleSetBuilder"))]
 pub struct RuleSet {
     // table setup
     decks: usize,
@@ -20,63 +424,237 @@ pub struct RuleSet {
     min_bet: f64,
     max_bet: f64,
     shuffle_kind: ShuffleKind,
+    shoe_exhaustion: ShoeExhaustion,
 
     // dealer rules
     dealer_on_soft_17: DealerOnSoft17,
 
     // blackjack payout
-    blackjack_payout: f64,
+    blackjack_payout: BlackjackPayout,
 
     // doubling down
-    double_down_whitelist: Vec<u64>,
+    double_rule: DoubleRule,
 
     // splitting
-    max_hands: u64,
-    can_play_slit_aces: bool,
-    das: bool, // can DD after splitting
+    split_rules: SplitRules,
+    split_aces: SplitAcesPolicy,
+    das: DoubleAfterSplit, // can DD after splitting
+
+    // surrendering (only as the first decision on two cards)
+    surrender: SurrenderRule,
+    surrender_policy: SurrenderPolicy,
+
+    // n-card charlie (non-busted hand with n cards wins automatically)
+    charlie: Option<u8>,
+
+    // dealer 22 pushes every live hand
+    push_22: bool,
+
+    // both dealer cards face up
+    double_exposure: bool,
+
+    // blackjack switch: each player plays two hands and may trade their second cards
+    #[cfg(feature = "variant-switch")]
+    switch: bool,
 
-    // surrendering (always late (after dealer checks for bj))
-    can_surrender: bool,
+    // free bet: the house funds doubles on hard 9-11 and splits of non-ten pairs
+    free_bet: bool,
+
+    // spanish 21: decks without pip tens, player naturals beat dealer naturals
+    #[cfg(feature = "variant-spanish21")]
+    spanish_21: bool,
+
+    settlement: SettlementPolicy,
+    #[cfg(feature = "variant-spanish21")]
+    bonuses: Option<BonusPayouts>,
+
+    // european no hole card: the dealer only checks for blackjack after the players act
+    no_hole_card: Option<EnhcLoss>,
+
+    // ante or commission taken every round, win or lose
+    round_fee: Option<RoundFee>,
+
+    tie_rule: TieRule,
+    bet_limit_policy: BetLimitPolicy,
 }
 
 impl RuleSet {
-    pub fn new(
+    pub fn builder() -> RuleSetBuilder {
+        RuleSetBuilder::new()
+    }
+
+    // 6D, S17, DAS, LS, split to 4, no resplitting aces
+    pub fn vegas_strip() -> Self {
+        Self::builder()
+            .s17()
+            .surrender(SurrenderRule::Late)
+            .build()
+            .expect("vegas strip preset is valid")
+    }
+
+    // 8D, S17, DAS, LS, split to 4, no resplitting aces
+    pub fn atlantic_city() -> Self {
+        Self::builder()
+            .decks(8)
+            .s17()
+            .surrender(SurrenderRule::Late)
+            .build()
+            .expect("atlantic city preset is valid")
+    }
+
+    // 6D, S17, double on 9-11 only, DAS, no resplits, no surrender
+    pub fn european() -> Self {
+        Self::builder()
+            .s17()
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .split_rules(SplitRules::new(2))
+            .build()
+            .expect("european preset is valid")
+    }
+
+    // 1D, H17, double any two, no DAS, no surrender, dealt to half the deck
+    pub fn single_deck_downtown() -> Self {
+        Self::builder()
+            .decks(1)
+            .shuffle_kind(ShuffleKind::Threshold(26))
+            .das(DoubleAfterSplit::None)
+            .build()
+            .expect("single deck downtown preset is valid")
+    }
+
+    // 8D, H17, both dealer cards shown, blackjack pays even money, ties lose, double on hard 9-11, no resplits
+    pub fn double_exposure_game() -> Self {
+        Self::builder()
+            .decks(8)
+            .blackjack_payout(BlackjackPayout::Custom(1.0))
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .split_rules(SplitRules::new(2))
+            .double_exposure(true)
+            .tie_rule(TieRule::DealerWinsExceptNaturals)
+            .build()
+            .expect("double exposure preset is valid")
+    }
+
+    // 6D, H17, two hands with switching, blackjack pays even money, dealer 22 pushes, DAS
+    #[cfg(feature = "variant-switch")]
+    pub fn blackjack_switch() -> Self {
+        Self::builder()
+            .blackjack_payout(BlackjackPayout::Custom(1.0))
+            .push_22(true)
+            .switch(true)
+            .build()
+            .expect("blackjack switch preset is valid")
+    }
+
+    // 6D, H17, free doubles on hard 9-11, free splits except tens, dealer 22 pushes
+    pub fn free_bet_game() -> Self {
+        Self::builder()
+            .push_22(true)
+            .free_bet(true)
+            .build()
+            .expect("free bet preset is valid")
+    }
+
+    // 6D spanish shoe, H17, double any number of cards, DAS, LS, resplit aces
+    #[cfg(feature = "variant-spanish21")]
+    pub fn spanish_21_game() -> Self {
+        Self::builder()
+            .double_rule(DoubleRule::new((3..=20).collect(), (12..=20).collect(), u8::MAX))
+            .split_aces(SplitAcesPolicy::new(true, 0))
+            .surrender(SurrenderRule::Late)
+            .spanish_21(true)
+            .settlement(SettlementPolicy::new(true, false, false))
+            .bonuses(Some(spanish_bonuses()))
+            .build()
+            .expect("spanish 21 preset is valid")
+    }
+
+    // every rule goes through `RuleSetBuilder`, which names each argument
+    #[allow(clippy::too_many_arguments)]
+    fn new(
         decks: usize,
         players: usize,
         min_bet: f64,
         max_bet: f64,
         shuffle_kind: ShuffleKind,
         dealer_on_soft_17: DealerOnSoft17,
-        blackjack_payout: f64,
-        double_down_whitelist: Vec<u64>,
-        max_hands: u64,
-        can_play_slit_aces: bool,
-        das: bool,
-        can_surrender: bool,
+        blackjack_payout: BlackjackPayout,
+        double_rule: DoubleRule,
+        split_rules: SplitRules,
+        split_aces: SplitAcesPolicy,
+        das: DoubleAfterSplit,
+        surrender: SurrenderRule,
+        surrender_policy: SurrenderPolicy,
+        charlie: Option<u8>,
+        push_22: bool,
+        shoe_exhaustion: ShoeExhaustion,
+        double_exposure: bool,
+        #[cfg(feature = "variant-switch")] switch: bool,
+        free_bet: bool,
+        #[cfg(feature = "variant-spanish21")] spanish_21: bool,
+        settlement: SettlementPolicy,
+        #[cfg(feature = "variant-spanish21")] bonuses: Option<BonusPayouts>,
+        no_hole_card: Option<EnhcLoss>,
+        round_fee: Option<RoundFee>,
+        tie_rule: TieRule,
+        bet_limit_policy: BetLimitPolicy,
     ) -> Result<Self, RuleSetError> {
         if decks == 0 {
             return Err(RuleSetError::InvalidDeckNumer);
         }
 
-        if players == 0 {
+        if players == 0 || players > HandId::MAX_SEATS {
             return Err(RuleSetError::InvalidPlayerNumber);
         }
 
-        if min_bet == 0.0 {
+        // NaN limits would pass both comparisons below and panic in `accept_bet`
+        if !min_bet.is_finite() || !max_bet.is_finite() {
             return Err(RuleSetError::InvalidBetRange);
         }
-        
-        if min_bet > max_bet {
+
+        if min_bet <= 0.0 || min_bet > max_bet {
             return Err(RuleSetError::InvalidBetRange);
         }
 
-        if max_hands < 2 {
+        let ratio: f64 = blackjack_payout.ratio();
+        if !ratio.is_finite() || ratio <= 0.0 || ratio > MAX_BLACKJACK_PAYOUT {
+            return Err(RuleSetError::InvalidBlackjackPayout);
+        }
+
+        if let ShuffleKind::Threshold(left) = shuffle_kind {
+            let cards: usize = decks * deck_size(#[cfg(feature = "variant-spanish21")] spanish_21);
+            let left: usize = left as usize;
+
+            if left >= cards {
+                return Err(RuleSetError::InvalidShuffleThreshold);
+            }
+
+            if cards - left < (players + 1) * CARDS_PER_SEAT {
+                return Err(RuleSetError::TooManyPlayers);
+            }
+        }
+
+        // a continuous shuffler takes its discards back, so it can neither void a round nor open new decks
+        if shuffle_kind == ShuffleKind::Continuous && shoe_exhaustion != ShoeExhaustion::Reshuffle {
+            return Err(RuleSetError::InvalidShoeExhaustion);
+        }
+
+        if split_rules.max_hands.iter().any(|&n| n < 2) {
             return Err(RuleSetError::InvalidMaxHands);
         }
 
-        for val in double_down_whitelist.clone() {
-            if val < 3 || val > 20 {
-                return Err(RuleSetError::InvalidDoubleDownWhitelist);
+        let mut totals = double_rule.hard.iter().chain(double_rule.soft.iter());
+        if double_rule.max_cards < 2 || totals.any(|v| !(3..=20).contains(&v)) {
+            return Err(RuleSetError::InvalidDoubleRule);
+        }
+
+        if round_fee.is_some_and(|fee| !fee.is_valid()) {
+            return Err(RuleSetError::InvalidRoundFee);
+        }
+
+        if let Some(n) = charlie {
+            if n < 3 {
+                return Err(RuleSetError::InvalidCharlie);
             }
         }
 
@@ -86,13 +664,30 @@ impl RuleSet {
             min_bet,
             max_bet,
             shuffle_kind,
+            shoe_exhaustion,
             dealer_on_soft_17,
             blackjack_payout,
-            double_down_whitelist,
-            max_hands,
-            can_play_slit_aces,
+            double_rule,
+            split_rules,
+            split_aces,
             das,
-            can_surrender,
+            surrender,
+            surrender_policy,
+            charlie,
+            push_22,
+            double_exposure,
+            #[cfg(feature = "variant-switch")]
+            switch,
+            free_bet,
+            #[cfg(feature = "variant-spanish21")]
+            spanish_21,
+            settlement,
+            #[cfg(feature = "variant-spanish21")]
+            bonuses,
+            no_hole_card,
+            round_fee,
+            tie_rule,
+            bet_limit_policy,
         })
     }
 
@@ -116,157 +711,967 @@ impl RuleSet {
         self.shuffle_kind
     }
 
+    pub fn shoe_exhaustion(&self) -> ShoeExhaustion {
+        self.shoe_exhaustion
+    }
+
     pub fn dealer_on_soft_17(&self) -> DealerOnSoft17 {
         self.dealer_on_soft_17
     }
 
-    pub fn blackjack_payout(&self) -> f64 {
+    pub fn blackjack_payout(&self) -> BlackjackPayout {
         self.blackjack_payout
     }
 
-    pub fn double_down_whitelist(&self) -> Vec<u64> {
-        self.double_down_whitelist.clone()
+    pub fn double_rule(&self) -> DoubleRule {
+        self.double_rule
     }
 
-    pub fn max_hands(&self) -> u64 {
-        self.max_hands
+    pub fn split_rules(&self) -> SplitRules {
+        self.split_rules
     }
 
-    pub fn can_play_slit_aces(&self) -> bool {
-        self.can_play_slit_aces
+    pub fn split_aces(&self) -> SplitAcesPolicy {
+        self.split_aces
     }
 
-    pub fn das(&self) -> bool {
+    pub fn das(&self) -> DoubleAfterSplit {
         self.das
     }
 
+    pub fn surrender(&self) -> SurrenderRule {
+        self.surrender
+    }
+
     pub fn can_surrender(&self) -> bool {
-        self.can_surrender
+        self.surrender != SurrenderRule::None
+    }
+
+    pub fn surrender_policy(&self) -> SurrenderPolicy {
+        self.surrender_policy
+    }
+
+    pub fn charlie(&self) -> Option<u8> {
+        self.charlie
+    }
+
+    pub fn push_22(&self) -> bool {
+        self.push_22
+    }
+
+    pub fn double_exposure(&self) -> bool {
+        self.double_exposure
+    }
+
+    #[cfg(feature = "variant-switch")]
+    pub fn switch(&self) -> bool {
+        self.switch
+    }
+
+    pub fn free_bet(&self) -> bool {
+        self.free_bet
+    }
+
+    #[cfg(feature = "variant-spanish21")]
+    pub fn spanish_21(&self) -> bool {
+        self.spanish_21
+    }
+
+    pub fn settlement(&self) -> SettlementPolicy {
+        self.settlement
+    }
+
+    #[cfg(feature = "variant-spanish21")]
+    pub fn bonuses(&self) -> Option<&BonusPayouts> {
+        self.bonuses.as_ref()
+    }
+
+    pub fn no_hole_card(&self) -> Option<EnhcLoss> {
+        self.no_hole_card
+    }
+
+    pub fn round_fee(&self) -> Option<RoundFee> {
+        self.round_fee
+    }
+
+    pub fn tie_rule(&self) -> TieRule {
+        self.tie_rule
+    }
+
+    pub fn bet_limit_policy(&self) -> BetLimitPolicy {
+        self.bet_limit_policy
+    }
+
+    pub fn deck_size(&self) -> usize {
+        deck_size(#[cfg(feature = "variant-spanish21")] self.spanish_21)
+    }
+
+    // the wager actually placed for an opening bet of `amount`
+    pub fn accept_bet(&self, amount: f64) -> Result<f64, BetError> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(BetError::InvalidAmount);
+        }
+
+        match self.bet_limit_policy {
+            BetLimitPolicy::Reject if amount < self.min_bet => Err(BetError::BelowMinimum),
+            BetLimitPolicy::Reject if amount > self.max_bet => Err(BetError::AboveMaximum),
+            _ => Ok(amount.clamp(self.min_bet, self.max_bet)),
+        }
+    }
+
+    // doubling for less is allowed, doubling for more is not; a split hand always carries the original bet
+    pub fn accept_double(&self, bet: f64, amount: f64) -> Result<f64, BetError> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(BetError::InvalidAmount);
+        }
+
+        match self.bet_limit_policy {
+            BetLimitPolicy::Reject if amount > bet => Err(BetError::AboveMaximum),
+            _ => Ok(amount.min(bet)),
+        }
+    }
+
+    // approximate change in house edge, as a fraction of the bet, for each rule that differs going
+    // from `self` to `other`; positive is worse for the player
+    pub fn edge_delta(&self, other: &RuleSet) -> Vec<(RuleDifference, f64)> {
+        let mut deltas: Vec<(RuleDifference, f64)> = Vec::new();
+
+        if self.decks != other.decks {
+            deltas.push((RuleDifference::Decks, deck_edge(other.decks) - deck_edge(self.decks)));
+        }
+
+        if self.dealer_on_soft_17 != other.dealer_on_soft_17 {
+            let h17: f64 = if other.dealer_on_soft_17 == DealerOnSoft17::H17 { H17_EDGE } else { -H17_EDGE };
+            deltas.push((RuleDifference::DealerOnSoft17, h17));
+        }
+
+        if self.blackjack_payout != other.blackjack_payout {
+            let naturals: NaturalFrequencies = NaturalFrequencies::for_rules(other);
+            let lost: f64 = naturals.payout_value(self.blackjack_payout) - naturals.payout_value(other.blackjack_payout);
+            deltas.push((RuleDifference::BlackjackPayout, lost));
+        }
+
+        if self.das != other.das {
+            deltas.push((RuleDifference::Das, das_edge(other.das) - das_edge(self.das)));
+        }
+
+        if self.surrender != other.surrender {
+            deltas.push((RuleDifference::Surrender, surrender_edge(other.surrender) - surrender_edge(self.surrender)));
+        }
+
+        if self.split_aces.resplit() != other.split_aces.resplit() {
+            let rsa: f64 = if other.split_aces.resplit() { RSA_EDGE } else { -RSA_EDGE };
+            deltas.push((RuleDifference::ResplitAces, rsa));
+        }
+
+        deltas
+    }
+
+    // one "field: before -> after" line per setting that differs, for logging what a run changed
+    pub fn diff(&self, other: &RuleSet) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let mut field = |name: &str, before: String, after: String| {
+            if before != after {
+                lines.push(format!("{}: {} -> {}", name, before, after));
+            }
+        };
+
+        field("decks", format!("{:?}", self.decks), format!("{:?}", other.decks));
+        field("players", format!("{:?}", self.players), format!("{:?}", other.players));
+        field("min_bet", format!("{:?}", self.min_bet), format!("{:?}", other.min_bet));
+        field("max_bet", format!("{:?}", self.max_bet), format!("{:?}", other.max_bet));
+        field("shuffle_kind", format!("{:?}", self.shuffle_kind), format!("{:?}", other.shuffle_kind));
+        field("shoe_exhaustion", format!("{:?}", self.shoe_exhaustion), format!("{:?}", other.shoe_exhaustion));
+        field("dealer_on_soft_17", format!("{:?}", self.dealer_on_soft_17), format!("{:?}", other.dealer_on_soft_17));
+        field("blackjack_payout", format!("{:?}", self.blackjack_payout), format!("{:?}", other.blackjack_payout));
+        field("double_rule", format!("{:?}", self.double_rule), format!("{:?}", other.double_rule));
+        field("split_rules", format!("{:?}", self.split_rules), format!("{:?}", other.split_rules));
+        field("split_aces", format!("{:?}", self.split_aces), format!("{:?}", other.split_aces));
+        field("das", format!("{:?}", self.das), format!("{:?}", other.das));
+        field("surrender", format!("{:?}", self.surrender), format!("{:?}", other.surrender));
+        field("surrender_policy", format!("{:?}", self.surrender_policy), format!("{:?}", other.surrender_policy));
+        field("charlie", format!("{:?}", self.charlie), format!("{:?}", other.charlie));
+        field("push_22", format!("{:?}", self.push_22), format!("{:?}", other.push_22));
+        field("double_exposure", format!("{:?}", self.double_exposure), format!("{:?}", other.double_exposure));
+        #[cfg(feature = "variant-switch")]
+        field("switch", format!("{:?}", self.switch), format!("{:?}", other.switch));
+        field("free_bet", format!("{:?}", self.free_bet), format!("{:?}", other.free_bet));
+        #[cfg(feature = "variant-spanish21")]
+        field("spanish_21", format!("{:?}", self.spanish_21), format!("{:?}", other.spanish_21));
+        field("settlement", format!("{:?}", self.settlement), format!("{:?}", other.settlement));
+        #[cfg(feature = "variant-spanish21")]
+        field("bonuses", format!("{:?}", self.bonuses), format!("{:?}", other.bonuses));
+        field("no_hole_card", format!("{:?}", self.no_hole_card), format!("{:?}", other.no_hole_card));
+        field("round_fee", format!("{:?}", self.round_fee), format!("{:?}", other.round_fee));
+        field("tie_rule", format!("{:?}", self.tie_rule), format!("{:?}", other.tie_rule));
+        field("bet_limit_policy", format!("{:?}", self.bet_limit_policy), format!("{:?}", other.bet_limit_policy));
+
+        lines
+    }
+}
+
+// placard style, e.g. "6D, H17, 3:2, DAS, LS"; doubling totals are only listed when restricted
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts: Vec<String> = vec![format!("{}D", self.decks)];
+
+        parts.push(match self.dealer_on_soft_17 {
+            DealerOnSoft17::H17 => "H17".to_string(),
+            DealerOnSoft17::S17 => "S17".to_string(),
+        });
+
+        parts.push(match self.blackjack_payout {
+            BlackjackPayout::ThreeToTwo => "3:2".to_string(),
+            BlackjackPayout::SixToFive => "6:5".to_string(),
+            BlackjackPayout::TwoToOne => "2:1".to_string(),
+            BlackjackPayout::Custom(ratio) => format!("{}:1", ratio),
+        });
+
+        let any: DoubleRule = DoubleRule::any_two_cards();
+        let double: DoubleRule = self.double_rule;
+        if double != any {
+            let mut label: String = match (double.hard == any.hard, double.soft == any.soft, double.soft.iter().next()) {
+                (true, true, _) => "DA".to_string(),
+                (_, _, None) => format!("D{}", double.hard),
+                _ => format!("D{} soft {}", double.hard, double.soft),
+            };
+
+            match double.max_cards {
+                2 => {},
+                u8::MAX => label.push_str(" any number of cards"),
+                n => label.push_str(&format!(" up to {} cards", n)),
+            }
+            parts.push(label);
+        }
+
+        match self.das {
+            DoubleAfterSplit::None => parts.push("NDAS".to_string()),
+            DoubleAfterSplit::Any => parts.push("DAS".to_string()),
+            DoubleAfterSplit::Totals(totals) => parts.push(format!("DAS {}", totals)),
+        }
+
+        match self.surrender {
+            SurrenderRule::None => {},
+            SurrenderRule::Late => parts.push("LS".to_string()),
+            SurrenderRule::LateNotVsAce => parts.push("LS not vs A".to_string()),
+            SurrenderRule::Early => parts.push("ES".to_string()),
+            SurrenderRule::EarlyExceptAce => parts.push("ES10".to_string()),
+        }
+
+        if self.split_aces.resplit() {
+            parts.push("RSA".to_string());
+        }
+
+        if let Some(n) = self.charlie {
+            parts.push(format!("{}CC", n));
+        }
+
+        if self.push_22 {
+            parts.push("push 22".to_string());
+        }
+
+        if self.no_hole_card.is_some() {
+            parts.push("ENHC".to_string());
+        }
+
+        let variants = [
+            (self.double_exposure, "double exposure"),
+            #[cfg(feature = "variant-switch")]
+            (self.switch, "switch"),
+            (self.free_bet, "free bet"),
+            #[cfg(feature = "variant-spanish21")]
+            (self.spanish_21, "spanish 21"),
+        ];
+        parts.extend(variants.iter().filter(|(on, _)| *on).map(|(_, name)| name.to_string()));
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[cfg(feature = "variant-spanish21")]
+fn spanish_bonuses() -> BonusPayouts {
+    Paytable::new(vec![
+        (Bonus::SixSevenEight, 1.5),
+        (Bonus::SixSevenEightSuited, 2.0),
+        (Bonus::SixSevenEightSpades, 3.0),
+        (Bonus::SevenSevenSeven, 1.5),
+        (Bonus::SevenSevenSevenSuited, 2.0),
+        (Bonus::SevenSevenSevenSpades, 3.0),
+    ]).expect("spanish 21 bonuses are valid")
+}
+
+// house edge coefficients as published for multi-deck basic strategy, as fractions of the bet
+const H17_EDGE: f64 = 0.0022;
+const DAS_EDGE: f64 = -0.0014;
+const RSA_EDGE: f64 = -0.0008;
+
+// nearly all of the gain comes from doubling 10 and 11
+fn das_edge(das: DoubleAfterSplit) -> f64 {
+    match das {
+        DoubleAfterSplit::None => 0.0,
+        DoubleAfterSplit::Any => DAS_EDGE,
+        DoubleAfterSplit::Totals(totals) if totals.contains(10) && totals.contains(11) => DAS_EDGE,
+        DoubleAfterSplit::Totals(_) => DAS_EDGE / 2.0,
+    }
+}
+
+// relative to eight decks
+fn deck_edge(decks: usize) -> f64 {
+    match decks {
+        1 => -0.0048,
+        2 => -0.0019,
+        3 => -0.0010,
+        4 => -0.0006,
+        5 => -0.0003,
+        6 => -0.0002,
+        7 => -0.0001,
+        _ => 0.0,
     }
 }
 
+// relative to no surrender
+fn surrender_edge(surrender: SurrenderRule) -> f64 {
+    match surrender {
+        SurrenderRule::None => 0.0,
+        SurrenderRule::Late => -0.0008,
+        SurrenderRule::LateNotVsAce => -0.0005,
+        SurrenderRule::EarlyExceptAce => -0.0024,
+        SurrenderRule::Early => -0.0039,
+    }
+}
+
+// defaults describe a common 6-deck shoe game: H17, 3:2, DAS, split to 4, one card to split aces
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RuleSetBuilder {
+    decks: usize,
+    players: usize,
+    min_bet: f64,
+    max_bet: f64,
+    shuffle_kind: Option<ShuffleKind>,
+    shoe_exhaustion: ShoeExhaustion,
+    dealer_on_soft_17: DealerOnSoft17,
+    blackjack_payout: BlackjackPayout,
+    double_rule: DoubleRule,
+    split_rules: SplitRules,
+    split_aces: SplitAcesPolicy,
+    das: DoubleAfterSplit,
+    surrender: SurrenderRule,
+    surrender_policy: SurrenderPolicy,
+    charlie: Option<u8>,
+    push_22: bool,
+    double_exposure: bool,
+    #[cfg(feature = "variant-switch")]
+    switch: bool,
+    free_bet: bool,
+    #[cfg(feature = "variant-spanish21")]
+    spanish_21: bool,
+    settlement: SettlementPolicy,
+    #[cfg(feature = "variant-spanish21")]
+    bonuses: Option<BonusPayouts>,
+    no_hole_card: Option<EnhcLoss>,
+    round_fee: Option<RoundFee>,
+    tie_rule: Option<TieRule>,
+    bet_limit_policy: BetLimitPolicy,
+}
+
+impl RuleSetBuilder {
+    pub fn new() -> Self {
+        Self {
+            decks: 6,
+            players: 1,
+            min_bet: 1.0,
+            max_bet: 100.0,
+            shuffle_kind: None,
+            shoe_exhaustion: ShoeExhaustion::Reshuffle,
+            dealer_on_soft_17: DealerOnSoft17::H17,
+            blackjack_payout: BlackjackPayout::ThreeToTwo,
+            double_rule: DoubleRule::any_two_cards(),
+            split_rules: SplitRules::new(4),
+            split_aces: SplitAcesPolicy::new(false, 0),
+            das: DoubleAfterSplit::Any,
+            surrender: SurrenderRule::None,
+            surrender_policy: SurrenderPolicy::new(false),
+            charlie: None,
+            push_22: false,
+            double_exposure: false,
+            #[cfg(feature = "variant-switch")]
+            switch: false,
+            free_bet: false,
+            #[cfg(feature = "variant-spanish21")]
+            spanish_21: false,
+            settlement: SettlementPolicy::new(false, false, false),
+            #[cfg(feature = "variant-spanish21")]
+            bonuses: None,
+            no_hole_card: None,
+            round_fee: None,
+            tie_rule: None,
+            bet_limit_policy: BetLimitPolicy::Reject,
+        }
+    }
+
+    pub fn decks(mut self, decks: usize) -> Self {
+        self.decks = decks;
+        self
+    }
+
+    pub fn players(mut self, players: usize) -> Self {
+        self.players = players;
+        self
+    }
+
+    pub fn bet_range(mut self, min_bet: f64, max_bet: f64) -> Self {
+        self.min_bet = min_bet;
+        self.max_bet = max_bet;
+        self
+    }
+
+    // defaults to reshuffling with a quarter of the shoe left
+    pub fn shuffle_kind(mut self, shuffle_kind: ShuffleKind) -> Self {
+        self.shuffle_kind = Some(shuffle_kind);
+        self
+    }
+
+    pub fn shoe_exhaustion(mut self, shoe_exhaustion: ShoeExhaustion) -> Self {
+        self.shoe_exhaustion = shoe_exhaustion;
+        self
+    }
+
+    pub fn h17(mut self) -> Self {
+        self.dealer_on_soft_17 = DealerOnSoft17::H17;
+        self
+    }
+
+    pub fn s17(mut self) -> Self {
+        self.dealer_on_soft_17 = DealerOnSoft17::S17;
+        self
+    }
+
+    pub fn dealer_on_soft_17(mut self, dealer_on_soft_17: DealerOnSoft17) -> Self {
+        self.dealer_on_soft_17 = dealer_on_soft_17;
+        self
+    }
+
+    pub fn blackjack_payout(mut self, blackjack_payout: BlackjackPayout) -> Self {
+        self.blackjack_payout = blackjack_payout;
+        self
+    }
+
+    pub fn double_rule(mut self, double_rule: DoubleRule) -> Self {
+        self.double_rule = double_rule;
+        self
+    }
+
+    pub fn split_rules(mut self, split_rules: SplitRules) -> Self {
+        self.split_rules = split_rules;
+        self
+    }
+
+    pub fn split_aces(mut self, split_aces: SplitAcesPolicy) -> Self {
+        self.split_aces = split_aces;
+        self
+    }
+
+    pub fn das(mut self, das: DoubleAfterSplit) -> Self {
+        self.das = das;
+        self
+    }
+
+    pub fn surrender(mut self, surrender: SurrenderRule) -> Self {
+        self.surrender = surrender;
+        self
+    }
+
+    pub fn surrender_policy(mut self, surrender_policy: SurrenderPolicy) -> Self {
+        self.surrender_policy = surrender_policy;
+        self
+    }
+
+    pub fn charlie(mut self, charlie: Option<u8>) -> Self {
+        self.charlie = charlie;
+        self
+    }
+
+    pub fn push_22(mut self, push_22: bool) -> Self {
+        self.push_22 = push_22;
+        self
+    }
+
+    pub fn double_exposure(mut self, double_exposure: bool) -> Self {
+        self.double_exposure = double_exposure;
+        self
+    }
+
+    #[cfg(feature = "variant-switch")]
+    pub fn switch(mut self, switch: bool) -> Self {
+        self.switch = switch;
+        self
+    }
+
+    pub fn free_bet(mut self, free_bet: bool) -> Self {
+        self.free_bet = free_bet;
+        self
+    }
+
+    #[cfg(feature = "variant-spanish21")]
+    pub fn spanish_21(mut self, spanish_21: bool) -> Self {
+        self.spanish_21 = spanish_21;
+        self
+    }
+
+    pub fn settlement(mut self, settlement: SettlementPolicy) -> Self {
+        self.settlement = settlement;
+        self
+    }
+
+    #[cfg(feature = "variant-spanish21")]
+    pub fn bonuses(mut self, bonuses: Option<BonusPayouts>) -> Self {
+        self.bonuses = bonuses;
+        self
+    }
+
+    pub fn no_hole_card(mut self, no_hole_card: Option<EnhcLoss>) -> Self {
+        self.no_hole_card = no_hole_card;
+        self
+    }
+
+    pub fn round_fee(mut self, round_fee: Option<RoundFee>) -> Self {
+        self.round_fee = round_fee;
+        self
+    }
+
+    // defaults to the dealer taking ties except naturals under double exposure, and a push otherwise
+    pub fn tie_rule(mut self, tie_rule: TieRule) -> Self {
+        self.tie_rule = Some(tie_rule);
+        self
+    }
+
+    pub fn bet_limit_policy(mut self, bet_limit_policy: BetLimitPolicy) -> Self {
+        self.bet_limit_policy = bet_limit_policy;
+        self
+    }
+
+    pub fn build(self) -> Result<RuleSet, RuleSetError> {
+        let shuffle_kind: ShuffleKind = self.shuffle_kind
+            .unwrap_or(default_shuffle_kind(self.decks, deck_size(#[cfg(feature = "variant-spanish21")] self.spanish_21)));
+        // double exposure games have always taken ties unless both hands are naturals
+        let tie_rule: TieRule = self.tie_rule
+            .unwrap_or(if self.double_exposure { TieRule::DealerWinsExceptNaturals } else { TieRule::Push });
+
+        RuleSet::new(
+            self.decks,
+            self.players,
+            self.min_bet,
+            self.max_bet,
+            shuffle_kind,
+            self.dealer_on_soft_17,
+            self.blackjack_payout,
+            self.double_rule,
+            self.split_rules,
+            self.split_aces,
+            self.das,
+            self.surrender,
+            self.surrender_policy,
+            self.charlie,
+            self.push_22,
+            self.shoe_exhaustion,
+            self.double_exposure,
+            #[cfg(feature = "variant-switch")] self.switch,
+            self.free_bet,
+            #[cfg(feature = "variant-spanish21")] self.spanish_21,
+            self.settlement,
+            #[cfg(feature = "variant-spanish21")] self.bonuses,
+            self.no_hole_card,
+            self.round_fee,
+            tie_rule,
+            self.bet_limit_policy,
+        )
+    }
+}
+
+impl Default for RuleSetBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<RuleSet> for RuleSetBuilder {
+    fn from(rules: RuleSet) -> Self {
+        Self {
+            decks: rules.decks,
+            players: rules.players,
+            min_bet: rules.min_bet,
+            max_bet: rules.max_bet,
+            // a derived threshold should follow later changes to the deck count
+            shuffle_kind: Some(rules.shuffle_kind).filter(|kind| *kind != default_shuffle_kind(rules.decks, rules.deck_size())),
+            shoe_exhaustion: rules.shoe_exhaustion,
+            dealer_on_soft_17: rules.dealer_on_soft_17,
+            blackjack_payout: rules.blackjack_payout,
+            double_rule: rules.double_rule,
+            split_rules: rules.split_rules,
+            split_aces: rules.split_aces,
+            das: rules.das,
+            surrender: rules.surrender,
+            surrender_policy: rules.surrender_policy,
+            charlie: rules.charlie,
+            push_22: rules.push_22,
+            double_exposure: rules.double_exposure,
+            #[cfg(feature = "variant-switch")]
+            switch: rules.switch,
+            free_bet: rules.free_bet,
+            #[cfg(feature = "variant-spanish21")]
+            spanish_21: rules.spanish_21,
+            settlement: rules.settlement,
+            #[cfg(feature = "variant-spanish21")]
+            bonuses: rules.bonuses,
+            no_hole_card: rules.no_hole_card,
+            round_fee: rules.round_fee,
+            tie_rule: Some(rules.tie_rule),
+            bet_limit_policy: rules.bet_limit_policy,
+        }
+    }
+}
+
+impl TryFrom<RuleSetBuilder> for RuleSet {
+    type Error = RuleSetError;
+
+    fn try_from(builder: RuleSetBuilder) -> Result<Self, Self::Error> {
+        builder.build()
+    }
+}
+
+// config files are picked by extension; missing fields fall back to the builder defaults
+#[cfg(feature = "serde")]
+impl RuleSet {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path: &Path = path.as_ref();
+        let text: String = fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+        match ConfigFormat::of(path)? {
+            ConfigFormat::Json => serde_json::from_str(&text).map_err(|e| ConfigError::Format(e.to_string())),
+            ConfigFormat::Toml => toml::from_str(&text).map_err(|e| ConfigError::Format(e.to_string())),
+        }
+    }
+
+    pub fn to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let path: &Path = path.as_ref();
+
+        let text: String = match ConfigFormat::of(path)? {
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(|e| ConfigError::Format(e.to_string()))?,
+            ConfigFormat::Toml => toml::to_string_pretty(self).map_err(|e| ConfigError::Format(e.to_string()))?,
+        };
+
+        fs::write(path, text).map_err(ConfigError::Io)
+    }
+}
+
+#[cfg(feature = "serde")]
+enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+#[cfg(feature = "serde")]
+impl ConfigFormat {
+    fn of(path: &Path) -> Result<Self, ConfigError> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Ok(Self::Json),
+            Some("toml") => Ok(Self::Toml),
+            _ => Err(ConfigError::UnknownFormat),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Format(String),
+    UnknownFormat,
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to access rule set config: {}", e),
+            Self::Format(e) => write!(f, "invalid rule set config: {}", e),
+            Self::UnknownFormat => write!(f, "rule set config must be a .json or .toml file"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error for ConfigError {}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub enum RuleSetError {
     InvalidDeckNumer,
     InvalidPlayerNumber,
     InvalidBetRange,
+    InvalidBlackjackPayout,
+    InvalidShuffleThreshold,
+    TooManyPlayers,
+    InvalidShoeExhaustion,
     InvalidMaxHands,
-    InvalidDoubleDownWhitelist,
+    InvalidDoubleRule,
+    InvalidCharlie,
+    InvalidRoundFee,
 }
 
 impl fmt::Display for RuleSetError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::InvalidDeckNumer => write!(f, "must have at least 1 deck"),
-            Self::InvalidPlayerNumber => write!(f, "must have at least 1 player"),
-            Self::InvalidBetRange => write!(f, "min bet must be at least 1 and not exceed max bet"),
-            Self::InvalidMaxHands => write!(f, "must have at least 2 max hands"),
-            Self::InvalidDoubleDownWhitelist => write!(f, "double down whitelist must contain some values from 3 to 20"),
+            Self::InvalidPlayerNumber => write!(f, "must have from 1 to 255 players"),
+            Self::InvalidBetRange => write!(f, "bet limits must be finite, with a positive min bet no greater than the max bet"),
+            Self::InvalidBlackjackPayout => write!(f, "blackjack must pay a positive ratio of at most 3 to 1"),
+            Self::InvalidShuffleThreshold => write!(f, "shuffle threshold must leave cards to deal; lower it or add decks"),
+            Self::TooManyPlayers => write!(f, "shoe cannot deal one round to every player before the shuffle; lower the threshold, add decks or remove players"),
+            Self::InvalidShoeExhaustion => write!(f, "a continuous shuffler always reshuffles its discards; use ShoeExhaustion::Reshuffle"),
+            Self::InvalidMaxHands => write!(f, "must have at least 2 max hands for every rank"),
+            Self::InvalidDoubleRule => write!(f, "double rule totals must be from 3 to 20 and allow at least 2 cards"),
+            Self::InvalidCharlie => write!(f, "charlie must require at least 3 cards"),
+            Self::InvalidRoundFee => write!(f, "round fee must be a non-negative amount or a commission below 100%"),
         }
     }
 }
 
 impl Error for RuleSetError {}
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum BetError {
+    InvalidAmount,
+    BelowMinimum,
+    AboveMaximum,
+}
+
+impl fmt::Display for BetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidAmount => write!(f, "wager must be a positive amount"),
+            Self::BelowMinimum => write!(f, "wager is below the table minimum"),
+            Self::AboveMaximum => write!(f, "wager is above the table maximum"),
+        }
+    }
+}
+
+impl Error for BetError {}
+
 #[cfg(test)]
 mod tests {
-    use crate::rule::{DealerOnSoft17, RuleSet, RuleSetError, ShuffleKind};
+    use crate::{card::Rank, rule::{BetError, BetLimitPolicy, BlackjackPayout, DealerOnSoft17, DoubleAfterSplit, DoubleRule, RoundFee, RuleDifference, RuleSet, RuleSetBuilder, RuleSetError, ShoeExhaustion, ShuffleKind, SplitRules, SurrenderRule, TieRule}};
 
     #[test]
     fn create_rulesets() {
-        assert!( RuleSet::new(
-            4,
-            4,
-            1.0,
-            1.0,
-            ShuffleKind::Continuous,
-            DealerOnSoft17::H17,
-            1.5,
-            vec![9, 10, 11],
-            3,
-            false,
-            false,
-            false,
-        ).is_ok() );
-
-        let invalid_deck_number = RuleSet::new(
-            0,
-            4,
-            1.0,
-            1.0,
-            ShuffleKind::Continuous,
-            DealerOnSoft17::H17,
-            1.5,
-            vec![9, 10, 11],
-            3,
-            false,
-            false,
-            false,
-        );
-        assert_eq!(Err(RuleSetError::InvalidDeckNumer), invalid_deck_number);
-
-        let invalid_player_number = RuleSet::new(
-            4,
-            0,
-            1.0,
-            1.0,
-            ShuffleKind::Continuous,
-            DealerOnSoft17::H17,
-            1.5,
-            vec![9, 10, 11],
-            3,
-            false,
-            false,
-            false,
-        );
-        assert_eq!(Err(RuleSetError::InvalidPlayerNumber), invalid_player_number);
-
-        let invalid_bet_range = RuleSet::new(
-            4,
-            4,
-            2.0,
-            1.0,
-            ShuffleKind::Continuous,
-            DealerOnSoft17::H17,
-            1.5,
-            vec![9, 10, 11],
-            3,
-            false,
-            false,
-            false,
-        );
-        assert_eq!(Err(RuleSetError::InvalidBetRange), invalid_bet_range);
-
-        let invalid_max_hands = RuleSet::new(
-            4,
-            4,
-            1.0,
-            1.0,
-            ShuffleKind::Continuous,
-            DealerOnSoft17::H17,
-            1.5,
-            vec![9, 10, 11],
-            1,
-            false,
-            false,
-            false,
+        let table = || RuleSet::builder()
+            .decks(4)
+            .players(4)
+            .bet_range(1.0, 1.0)
+            .shuffle_kind(ShuffleKind::Continuous)
+            .double_rule(DoubleRule::hard_only(vec![9, 10, 11]))
+            .split_rules(SplitRules::new(3));
+
+        assert!(table().build().is_ok());
+        assert_eq!(Err(RuleSetError::InvalidDeckNumer), table().decks(0).build());
+        assert_eq!(Err(RuleSetError::InvalidPlayerNumber), table().players(0).build());
+        assert_eq!(Err(RuleSetError::InvalidBetRange), table().bet_range(2.0, 1.0).build());
+        assert_eq!(Err(RuleSetError::InvalidMaxHands), table().split_rules(SplitRules::new(1)).build());
+        assert_eq!(
+            Err(RuleSetError::InvalidMaxHands),
+            table().split_rules(SplitRules::new(4).with_max_hands(Rank::Ace, 1)).build(),
         );
-        assert_eq!(Err(RuleSetError::InvalidMaxHands), invalid_max_hands);
-
-        let invalid_double_down_whitelist = RuleSet::new(
-            4,
-            4,
-            1.0,
-            1.0,
-            ShuffleKind::Continuous,
-            DealerOnSoft17::H17,
-            1.5,
-            vec![9, 10, 11, 21],
-            3,
-            false,
-            false,
-            false,
+        assert_eq!(Err(RuleSetError::InvalidDoubleRule), table().double_rule(DoubleRule::hard_only(vec![9, 10, 11, 21])).build());
+        assert_eq!(Err(RuleSetError::InvalidCharlie), table().charlie(Some(2)).build());
+    }
+
+    #[test]
+    fn build_rulesets() {
+        let rules = RuleSet::builder().build().unwrap();
+        assert_eq!(6, rules.decks());
+        assert_eq!(DealerOnSoft17::H17, rules.dealer_on_soft_17());
+        assert_eq!(ShuffleKind::Threshold(78), rules.shuffle_kind());
+        assert_eq!(DoubleAfterSplit::Any, rules.das());
+
+        let rules = RuleSet::builder().decks(2).s17().das(DoubleAfterSplit::None).surrender(SurrenderRule::Late).build().unwrap();
+        assert_eq!(2, rules.decks());
+        assert_eq!(DealerOnSoft17::S17, rules.dealer_on_soft_17());
+        assert_eq!(ShuffleKind::Threshold(26), rules.shuffle_kind());
+        assert_eq!(DoubleAfterSplit::None, rules.das());
+        assert!(rules.can_surrender());
+
+        assert_eq!(Err(RuleSetError::InvalidDeckNumer), RuleSet::builder().decks(0).build());
+        assert_eq!(Err(RuleSetError::InvalidBetRange), RuleSet::builder().bet_range(5.0, 1.0).build());
+        assert_eq!(Err(RuleSetError::InvalidBetRange), RuleSet::builder().bet_range(f64::NAN, 100.0).build());
+        assert_eq!(Err(RuleSetError::InvalidBetRange), RuleSet::builder().bet_range(1.0, f64::NAN).build());
+        assert_eq!(Err(RuleSetError::InvalidBetRange), RuleSet::builder().bet_range(1.0, f64::INFINITY).build());
+        assert_eq!(Err(RuleSetError::InvalidBetRange), RuleSet::builder().bet_range(-5.0, 100.0).build());
+        assert_eq!(Err(RuleSetError::InvalidBetRange), RuleSet::builder().bet_range(-5.0, -1.0).build());
+        assert!(RuleSet::builder().bet_range(0.5, 0.5).build().is_ok());
+        assert_eq!(Err(RuleSetError::InvalidBlackjackPayout), RuleSet::builder().blackjack_payout(BlackjackPayout::Custom(0.0)).build());
+        assert_eq!(Err(RuleSetError::InvalidBlackjackPayout), RuleSet::builder().blackjack_payout(BlackjackPayout::Custom(f64::NAN)).build());
+        assert!(RuleSet::builder().blackjack_payout(BlackjackPayout::Custom(1.0)).build().is_ok());
+        assert_eq!(Err(RuleSetError::InvalidBlackjackPayout), RuleSet::builder().blackjack_payout(BlackjackPayout::Custom(15.0)).build());
+
+        let single = RuleSet::builder().decks(1);
+        assert_eq!(Err(RuleSetError::InvalidShuffleThreshold), single.clone().shuffle_kind(ShuffleKind::Threshold(52)).build());
+        assert_eq!(Err(RuleSetError::TooManyPlayers), single.clone().players(7).shuffle_kind(ShuffleKind::Threshold(40)).build());
+        assert!(single.players(7).shuffle_kind(ShuffleKind::Continuous).build().is_ok());
+        let continuous = RuleSet::builder().shuffle_kind(ShuffleKind::Continuous);
+        assert_eq!(Err(RuleSetError::InvalidShoeExhaustion), continuous.clone().shoe_exhaustion(ShoeExhaustion::Void).build());
+        assert_eq!(Err(RuleSetError::InvalidShoeExhaustion), continuous.shoe_exhaustion(ShoeExhaustion::NewShoe).build());
+        assert!(RuleSet::builder().players(255).shuffle_kind(ShuffleKind::Continuous).build().is_ok());
+        assert_eq!(Err(RuleSetError::InvalidPlayerNumber), RuleSet::builder().players(256).shuffle_kind(ShuffleKind::Continuous).build());
+
+        // only an explicit threshold carries over a change in deck count
+        let six: RuleSet = RuleSet::builder().decks(6).build().unwrap();
+        assert_eq!(ShuffleKind::Threshold(13), RuleSetBuilder::from(six).decks(1).build().unwrap().shuffle_kind());
+        let set: RuleSet = RuleSet::builder().decks(2).shuffle_kind(ShuffleKind::Threshold(20)).build().unwrap();
+        assert_eq!(ShuffleKind::Threshold(20), RuleSetBuilder::from(set).decks(1).build().unwrap().shuffle_kind());
+        assert_eq!(Err(RuleSetError::InvalidRoundFee), RuleSet::builder().round_fee(Some(RoundFee::Flat(-1.0))).build());
+        assert_eq!(Err(RuleSetError::InvalidRoundFee), RuleSet::builder().round_fee(Some(RoundFee::Commission(1.0))).build());
+    }
+
+    #[test]
+    fn presets() {
+        let strip = RuleSet::vegas_strip();
+        assert_eq!(6, strip.decks());
+        assert_eq!(DealerOnSoft17::S17, strip.dealer_on_soft_17());
+        assert!(strip.can_surrender());
+
+        assert_eq!(8, RuleSet::atlantic_city().decks());
+
+        let european = RuleSet::european();
+        assert_eq!(DoubleRule::hard_only(vec![9, 10, 11]), european.double_rule());
+        assert_eq!(vec![9, 10, 11], european.double_rule().hard().to_vec());
+        assert_eq!(2, european.split_rules().max_hands(Rank::Eight));
+        assert!(!european.can_surrender());
+
+        let downtown = RuleSet::single_deck_downtown();
+        assert_eq!(1, downtown.decks());
+        assert_eq!(DealerOnSoft17::H17, downtown.dealer_on_soft_17());
+        assert_eq!(DoubleAfterSplit::None, downtown.das());
+
+        let exposure = RuleSet::double_exposure_game();
+        assert!(exposure.double_exposure());
+        assert_eq!(TieRule::DealerWinsExceptNaturals, exposure.tie_rule());
+        assert_eq!(1.0, exposure.blackjack_payout().ratio());
+        assert_eq!(TieRule::DealerWinsExceptNaturals, RuleSet::builder().double_exposure(true).build().unwrap().tie_rule());
+        assert_eq!(TieRule::Push, RuleSet::builder().double_exposure(true).tie_rule(TieRule::Push).build().unwrap().tie_rule());
+
+        assert!(RuleSet::free_bet_game().free_bet());
+
+        #[cfg(feature = "variant-switch")]
+        {
+            let switch = RuleSet::blackjack_switch();
+            assert!(switch.switch());
+            assert!(switch.push_22());
+        }
+
+        #[cfg(feature = "variant-spanish21")]
+        {
+            let spanish = RuleSet::spanish_21_game();
+            assert_eq!(48, spanish.deck_size());
+            assert_eq!(ShuffleKind::Threshold(72), spanish.shuffle_kind());
+        }
+    }
+
+    #[test]
+    fn rule_edge_deltas() {
+        let vegas = RuleSet::vegas_strip();
+        assert!(vegas.edge_delta(&vegas).is_empty());
+
+        let downtown = RuleSetBuilder::from(vegas.clone())
+            .dealer_on_soft_17(DealerOnSoft17::H17)
+            .blackjack_payout(BlackjackPayout::SixToFive)
+            .build()
+            .unwrap();
+        let deltas = vegas.edge_delta(&downtown);
+        assert_eq!(RuleDifference::DealerOnSoft17, deltas[0].0);
+        assert!((deltas[0].1 - 0.0022).abs() < 1e-12);
+        assert_eq!(RuleDifference::BlackjackPayout, deltas[1].0);
+        assert!((deltas[1].1 - 0.0136).abs() < 0.0005);
+
+        // going back undoes every difference
+        let total: f64 = downtown.edge_delta(&vegas).iter().chain(&deltas).map(|(_, d)| d).sum();
+        assert!(total.abs() < 1e-12);
+
+        let single = RuleSetBuilder::from(vegas.clone()).decks(1).build().unwrap();
+        assert_eq!(vec![(RuleDifference::Decks, -0.0046)], vegas.edge_delta(&single));
+    }
+
+    #[test]
+    fn ruleset_summary_and_diff() {
+        assert_eq!("6D, S17, 3:2, DAS, LS", RuleSet::vegas_strip().to_string());
+        assert_eq!("1D, H17, 3:2, NDAS", RuleSet::single_deck_downtown().to_string());
+        assert_eq!("8D, H17, 1:1, D9-11, DAS, double exposure", RuleSet::double_exposure_game().to_string());
+
+        let placard = |double_rule: DoubleRule| RuleSet::builder().double_rule(double_rule).build().unwrap().to_string();
+        assert_eq!("6D, H17, 3:2, D9-11 soft 13-18, DAS", placard(DoubleRule::new(vec![9, 10, 11], (13..=18).collect(), 2)));
+        assert_eq!("6D, H17, 3:2, D10/11 up to 3 cards, DAS", placard(DoubleRule::new(vec![10, 11], vec![], 3)));
+        #[cfg(feature = "variant-spanish21")]
+        assert_eq!("6D, H17, 3:2, DA any number of cards, DAS, LS, RSA, spanish 21", RuleSet::spanish_21_game().to_string());
+
+        let strip = RuleSet::vegas_strip();
+        assert!(strip.diff(&strip).is_empty());
+        assert_eq!(
+            vec!["decks: 6 -> 8".to_string(), "shuffle_kind: Threshold(78) -> Threshold(104)".to_string()],
+            strip.diff(&RuleSet::atlantic_city()),
         );
-        assert_eq!(Err(RuleSetError::InvalidDoubleDownWhitelist), invalid_double_down_whitelist);
+    }
+
+    #[test]
+    fn bet_limits() {
+        let reject = RuleSet::builder().bet_range(5.0, 500.0).build().unwrap();
+        assert_eq!(Ok(25.0), reject.accept_bet(25.0));
+        assert_eq!(Err(BetError::BelowMinimum), reject.accept_bet(1.0));
+        assert_eq!(Err(BetError::AboveMaximum), reject.accept_bet(1000.0));
+        assert_eq!(Err(BetError::InvalidAmount), reject.accept_bet(f64::NAN));
+        assert_eq!(Ok(10.0), reject.accept_double(25.0, 10.0));
+        assert_eq!(Err(BetError::AboveMaximum), reject.accept_double(25.0, 50.0));
+
+        let clamp = RuleSetBuilder::from(reject).bet_limit_policy(BetLimitPolicy::Clamp).build().unwrap();
+        assert_eq!(Ok(5.0), clamp.accept_bet(1.0));
+        assert_eq!(Ok(500.0), clamp.accept_bet(1000.0));
+        assert_eq!(Ok(25.0), clamp.accept_double(25.0, 50.0));
+        assert_eq!(Err(BetError::InvalidAmount), clamp.accept_bet(-5.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn rulesets_round_trip_through_config_files() {
+        let dir = std::env::temp_dir().join(format!("blackjack-rules-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        #[cfg(feature = "variant-spanish21")]
+        let spanish: RuleSet = {
+            use crate::{paytable::Paytable, rule::Bonus};
+
+            let bonuses = Paytable::new(vec![(Bonus::SevenSevenSeven, 1.5), (Bonus::SixSevenEightSpades, 3.0)]).unwrap();
+            RuleSet::builder().spanish_21(true).bonuses(Some(bonuses)).build().unwrap()
+        };
+
+        for rules in [RuleSet::european(), #[cfg(feature = "variant-spanish21")] spanish] {
+            for name in ["rules.json", "rules.toml"] {
+                rules.to_path(dir.join(name)).unwrap();
+                assert_eq!(rules, RuleSet::from_path(dir.join(name)).unwrap());
+            }
+        }
+
+        std::fs::write(dir.join("partial.toml"), "decks = 2\ndealer_on_soft_17 = \"S17\"\n").unwrap();
+        let partial = RuleSet::from_path(dir.join("partial.toml")).unwrap();
+        assert_eq!(2, partial.decks());
+        assert_eq!(DealerOnSoft17::S17, partial.dealer_on_soft_17());
+        assert_eq!(DoubleAfterSplit::Any, partial.das());
+
+        // configs written before TieRule existed keep the old double exposure ties
+        std::fs::write(dir.join("exposure.toml"), "double_exposure = true\n").unwrap();
+        assert_eq!(TieRule::DealerWinsExceptNaturals, RuleSet::from_path(dir.join("exposure.toml")).unwrap().tie_rule());
+
+        std::fs::write(dir.join("invalid.json"), "{ \"decks\": 0 }").unwrap();
+        assert!(RuleSet::from_path(dir.join("invalid.json")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use crate::{card::{Card, Rank}, shuffle::Procedure};
+use rand::Rng;
+
+// the card dealt right before an ace, and where in the shoe that happened
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct KeyCard {
+    key: Card,
+    ace: Card,
+    position: usize,
+}
+
+impl KeyCard {
+    pub fn key(&self) -> Card {
+        self.key
+    }
+
+    pub fn ace(&self) -> Card {
+        self.ace
+    }
+
+    // how many cards into the shoe the ace was dealt
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+// fed every card as it is dealt through a shoe
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Default)]
+pub struct AceSequencer {
+    last: Option<Card>,
+    dealt: usize,
+    keys: Vec<KeyCard>,
+}
+
+impl AceSequencer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, card: Card) {
+        self.dealt += 1;
+
+        if let (Rank::Ace, Some(key)) = (card.rank(), self.last) {
+            self.keys.push(KeyCard { key, ace: card, position: self.dealt });
+        }
+        self.last = Some(card);
+    }
+
+    pub fn observe_all<I>(&mut self, cards: I)
+        where I: IntoIterator<Item = Card>
+    {
+        for card in cards {
+            self.observe(card);
+        }
+    }
+
+    pub fn keys(&self) -> &[KeyCard] {
+        &self.keys
+    }
+
+    // the keys from the shoe just finished; the sequencer starts over for the next one
+    pub fn end_shoe(&mut self) -> Vec<KeyCard> {
+        std::mem::take(self).keys
+    }
+}
+
+// how far apart two cards that went into the shuffle together come out, in cards dealt from the key to the ace
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash, Default)]
+pub struct Displacement {
+    gaps: BTreeMap<i64, u64>,
+    pairs: u64,
+}
+
+impl Displacement {
+    // shuffles a pile of `cards` positions `trials` times and measures every adjacent pair
+    pub fn estimate<R>(procedure: &Procedure, cards: usize, trials: usize, rng: &mut R) -> Self
+        where R: Rng + ?Sized
+    {
+        let mut displacement = Self::default();
+
+        for _ in 0..trials {
+            let mut pile: Vec<usize> = (0..cards).collect();
+            procedure.apply(&mut pile, rng);
+
+            let mut place: Vec<i64> = vec![0; cards];
+            for (i, &p) in pile.iter().enumerate() {
+                place[p] = i as i64;
+            }
+
+            // the key sat right above its ace, and the top of the pile is dealt first
+            for ace in 0..cards.saturating_sub(1) {
+                *displacement.gaps.entry(place[ace + 1] - place[ace]).or_insert(0) += 1;
+                displacement.pairs += 1;
+            }
+        }
+
+        displacement
+    }
+
+    // chance the ace comes out within `window` cards after its key
+    pub fn probability_within(&self, window: usize) -> f64 {
+        if self.pairs == 0 {
+            return 0.0;
+        }
+
+        let hits: u64 = self.gaps.range(1..=window as i64).map(|(_, n)| n).sum();
+
+        hits as f64 / self.pairs as f64
+    }
+
+    pub fn gaps(&self) -> &BTreeMap<i64, u64> {
+        &self.gaps
+    }
+}
+
+// an ace expected within the next `window` cards, counted from the card after the key
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct Prediction {
+    pub key: KeyCard,
+    pub window: usize,
+    pub probability: f64,
+}
+
+// watches the next shoe for the keys recorded in the last one
+#[derive(Debug, PartialEq, Clone)]
+pub struct AceTracker {
+    keys: Vec<KeyCard>,
+    window: usize,
+    probability: f64,
+}
+
+impl AceTracker {
+    pub fn new(keys: Vec<KeyCard>, displacement: &Displacement, window: usize) -> Self {
+        Self { keys, window, probability: displacement.probability_within(window) }
+    }
+
+    // each key is only predicted from once, by the first copy of it seen
+    pub fn see(&mut self, card: Card) -> Option<Prediction> {
+        let i: usize = self.keys.iter().position(|k| k.key == card)?;
+
+        Some(Prediction { key: self.keys.remove(i), window: self.window, probability: self.probability })
+    }
+
+    pub fn keys_left(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, sequencing::{AceSequencer, AceTracker, Displacement}, shuffle::{Procedure, ShuffleStep}};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn keys_to_predictions() {
+        let key = Card::new(Suit::Hearts, Rank::Nine);
+        let ace = Card::new(Suit::Clubs, Rank::Ace);
+
+        let mut sequencer = AceSequencer::new();
+        sequencer.observe_all([Card::new(Suit::Spades, Rank::Two), key, ace, Card::new(Suit::Spades, Rank::Ace)]);
+        assert_eq!(2, sequencer.keys().len());
+        assert_eq!((key, ace, 3), (sequencer.keys()[0].key(), sequencer.keys()[0].ace(), sequencer.keys()[0].position()));
+
+        let keys = sequencer.end_shoe();
+        assert!(sequencer.keys().is_empty());
+
+        // without a shuffle every ace stays right behind its key
+        let mut rng = StdRng::seed_from_u64(8);
+        let untouched = Displacement::estimate(&Procedure::new(Vec::new()), 52, 10, &mut rng);
+        assert_eq!(1.0, untouched.probability_within(1));
+
+        let mut tracker = AceTracker::new(keys, &untouched, 1);
+        assert_eq!(None, tracker.see(Card::new(Suit::Diamonds, Rank::Nine)));
+        let prediction = tracker.see(key).unwrap();
+        assert_eq!((ace, 1.0), (prediction.key.ace(), prediction.probability));
+        assert_eq!(1, tracker.keys_left());
+    }
+
+    #[test]
+    fn shuffles_spread_the_pairs() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let one_riffle = Displacement::estimate(&Procedure::new(vec![ShuffleStep::Riffle]), 104, 200, &mut rng);
+        let washed = Displacement::estimate(&Procedure::new(vec![ShuffleStep::Wash]), 104, 200, &mut rng);
+
+        assert!(one_riffle.probability_within(6) > 0.8);
+        assert!(washed.probability_within(6) < 0.1);
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+use crate::{card::Card, deck::Deck, rule::RuleSet};
+use rand::{seq::SliceRandom, Rng};
+
+// physical shuffles for shuffle-tracking work; the pile's top card is the last one in the vec, and anything
+// Copy can be shuffled so a tracker can follow positions instead of cards
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum ShuffleStep {
+    Riffle,
+    // strip off this many packets of random size
+    Strip(usize),
+    Box,
+    Wash,
+    Cut,
+}
+
+impl ShuffleStep {
+    pub fn apply<T, R>(&self, cards: &mut Vec<T>, rng: &mut R)
+        where T: Copy, R: Rng + ?Sized
+    {
+        match self {
+            Self::Riffle => riffle(cards, rng),
+            Self::Strip(packets) => strip(cards, *packets, rng),
+            Self::Box => box_shuffle(cards, rng),
+            Self::Wash => wash(cards, rng),
+            Self::Cut => cut(cards, rng),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
+pub struct Procedure {
+    steps: Vec<ShuffleStep>,
+}
+
+impl Procedure {
+    pub fn new(steps: Vec<ShuffleStep>) -> Self {
+        Self { steps }
+    }
+
+    // riffle, riffle, strip, riffle, cut as dealt by hand in most shoe games
+    pub fn casino() -> Self {
+        Self::new(vec![ShuffleStep::Riffle, ShuffleStep::Riffle, ShuffleStep::Strip(4), ShuffleStep::Riffle, ShuffleStep::Cut])
+    }
+
+    pub fn steps(&self) -> Vec<ShuffleStep> {
+        self.steps.clone()
+    }
+
+    pub fn apply<T, R>(&self, cards: &mut Vec<T>, rng: &mut R)
+        where T: Copy, R: Rng + ?Sized
+    {
+        for step in &self.steps {
+            step.apply(cards, rng);
+        }
+    }
+}
+
+// gilbert-shannon-reeds: a binomial cut, then cards drop from each half in proportion to its size
+pub fn riffle<T, R>(cards: &mut Vec<T>, rng: &mut R)
+    where T: Copy, R: Rng + ?Sized
+{
+    let n: usize = cards.len();
+    let cut: usize = (0..n).filter(|_| rng.gen_bool(0.5)).count();
+    let (bottom, top) = cards.split_at(cut);
+
+    let mut riffled: Vec<T> = Vec::with_capacity(n);
+    let (mut i, mut j) = (0, 0);
+    while i < bottom.len() || j < top.len() {
+        let left: usize = bottom.len() - i;
+        let right: usize = top.len() - j;
+
+        if rng.gen_range(0..left + right) < left {
+            riffled.push(bottom[i]);
+            i += 1;
+        } else {
+            riffled.push(top[j]);
+            j += 1;
+        }
+    }
+
+    *cards = riffled;
+}
+
+// packets come off the top and land in a new pile, so their order is reversed but each stays intact
+pub fn strip<T, R>(cards: &mut Vec<T>, packets: usize, rng: &mut R)
+    where T: Copy, R: Rng + ?Sized
+{
+    if packets < 2 || cards.len() < packets {
+        return;
+    }
+
+    // packet boundaries counted from the top
+    let mut bounds: Vec<usize> = rand::seq::index::sample(rng, cards.len() - 1, packets - 1)
+        .into_iter()
+        .map(|b| b + 1)
+        .collect();
+    bounds.sort();
+
+    *cards = packets_from_top(cards, &bounds);
+}
+
+// four even packets, give or take a few cards, restacked top to bottom
+pub fn box_shuffle<T, R>(cards: &mut Vec<T>, rng: &mut R)
+    where T: Copy, R: Rng + ?Sized
+{
+    let n: usize = cards.len();
+    if n < 8 {
+        return;
+    }
+
+    let jitter: usize = (n / 16).max(1);
+    let bounds: Vec<usize> = (1..4)
+        .map(|k| k * n / 4 + rng.gen_range(0..=2 * jitter) - jitter)
+        .collect();
+
+    *cards = packets_from_top(cards, &bounds);
+}
+
+// the cards are spread face down and mixed by hand, which is as close to uniform as a shuffle gets
+pub fn wash<T, R>(cards: &mut [T], rng: &mut R)
+    where T: Copy, R: Rng + ?Sized
+{
+    cards.shuffle(rng);
+}
+
+// somewhere in the middle half of the pile
+pub fn cut<T, R>(cards: &mut [T], rng: &mut R)
+    where T: Copy, R: Rng + ?Sized
+{
+    let n: usize = cards.len();
+    if n < 4 {
+        return;
+    }
+
+    cards.rotate_left(rng.gen_range(n / 4..=3 * n / 4));
+}
+
+// continuous shuffling machine: discards ride an elevator for `latency` dealt cards before they
+// drop back into the shelves, and every card dealt is drawn at random from the shelves
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Csm {
+    shelves: Vec<Card>,
+    // each card with the dealt count at which it drops back in
+    elevator: VecDeque<(u64, Card)>,
+    latency: u64,
+    dealt: u64,
+}
+
+impl Csm {
+    pub fn new(deck: Deck, latency: u64) -> Self {
+        Self { shelves: deck.stack().to_vec(), elevator: VecDeque::new(), latency, dealt: 0 }
+    }
+
+    // about a deck's worth of cards goes out before a discard can come back
+    pub fn for_rules(rules: &RuleSet) -> Self {
+        Self::new(Deck::for_rules(rules), rules.deck_size() as u64)
+    }
+
+    pub fn deal<R>(&mut self, rng: &mut R) -> Option<Card>
+        where R: Rng + ?Sized
+    {
+        // an empty machine gives up whatever is still riding the elevator
+        if self.shelves.is_empty() {
+            self.shelves.extend(self.elevator.drain(..).map(|(_, c)| c));
+        }
+
+        if self.shelves.is_empty() {
+            return None;
+        }
+
+        let card: Card = self.shelves.swap_remove(rng.gen_range(0..self.shelves.len()));
+        self.dealt += 1;
+
+        while self.elevator.front().is_some_and(|&(due, _)| due <= self.dealt) {
+            let (_, card) = self.elevator.pop_front().expect("front was just checked");
+            self.shelves.push(card);
+        }
+
+        Some(card)
+    }
+
+    pub fn discard(&mut self, cards: Vec<Card>) {
+        let due: u64 = self.dealt + self.latency;
+        self.elevator.extend(cards.into_iter().map(|c| (due, c)));
+    }
+
+    pub fn shelved(&self) -> usize {
+        self.shelves.len()
+    }
+
+    pub fn in_elevator(&self) -> usize {
+        self.elevator.len()
+    }
+
+    pub fn latency(&self) -> u64 {
+        self.latency
+    }
+}
+
+// `bounds` are ascending card counts from the top; the top packet ends up at the bottom
+fn packets_from_top<T>(cards: &[T], bounds: &[usize]) -> Vec<T>
+    where T: Copy
+{
+    let n: usize = cards.len();
+    let mut restacked: Vec<T> = Vec::with_capacity(n);
+    let mut taken: usize = 0;
+
+    for &b in bounds.iter().chain(std::iter::once(&n)) {
+        restacked.extend_from_slice(&cards[n - b..n - taken]);
+        taken = b;
+    }
+
+    restacked
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::Card, deck::Deck, rule::{RuleSet, ShuffleKind}, shuffle::{riffle, strip, Csm, Procedure, ShuffleStep}};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn sorted(cards: &[Card]) -> Vec<Card> {
+        let mut cards: Vec<Card> = cards.to_vec();
+        cards.sort();
+        cards
+    }
+
+    #[test]
+    fn physical_shuffles_keep_the_cards() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let original: Vec<Card> = Deck::new_shoe(1).stack().to_vec();
+
+        // one riffle leaves at most two rising sequences of the original positions
+        let mut cards: Vec<Card> = original.clone();
+        riffle(&mut cards, &mut rng);
+        assert_eq!(sorted(&original), sorted(&cards));
+        let places: Vec<usize> = original.iter().map(|o| cards.iter().position(|c| c == o).unwrap()).collect();
+        assert!(places.windows(2).filter(|w| w[1] < w[0]).count() <= 1);
+
+        // a strip keeps every packet intact, so only the packet seams break the order
+        let mut cards: Vec<Card> = original.clone();
+        strip(&mut cards, 4, &mut rng);
+        let positions: Vec<usize> = cards.iter().map(|c| original.iter().position(|o| o == c).unwrap()).collect();
+        assert_eq!(3, positions.windows(2).filter(|w| w[1] != w[0] + 1).count());
+
+        for step in [ShuffleStep::Box, ShuffleStep::Wash, ShuffleStep::Cut] {
+            let mut cards: Vec<Card> = original.clone();
+            step.apply(&mut cards, &mut rng);
+            assert_eq!(sorted(&original), sorted(&cards));
+        }
+    }
+
+    #[test]
+    fn seeded_procedures_repeat() {
+        let shuffled = |seed: u64| {
+            let mut deck = Deck::new_shoe(6);
+            deck.shuffle_by(&Procedure::casino(), &mut StdRng::seed_from_u64(seed));
+            deck
+        };
+
+        assert_eq!(shuffled(11), shuffled(11));
+        assert_ne!(shuffled(11), shuffled(12));
+        assert_eq!(312, shuffled(11).cards_left());
+    }
+
+    #[test]
+    fn csm_holds_discards_back() {
+        let rules = RuleSet::builder().decks(2).shuffle_kind(ShuffleKind::Continuous).build().unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut csm = Csm::for_rules(&rules);
+        assert_eq!(104, csm.shelved());
+        assert_eq!(52, csm.latency());
+
+        let round: Vec<Card> = (0..10).map(|_| csm.deal(&mut rng).unwrap()).collect();
+        csm.discard(round);
+        assert_eq!(10, csm.in_elevator());
+
+        // nothing from the last round can come out again for a deck's worth of cards
+        for _ in 0..51 {
+            csm.deal(&mut rng).unwrap();
+        }
+        assert_eq!(10, csm.in_elevator());
+
+        csm.deal(&mut rng);
+        assert_eq!(0, csm.in_elevator());
+        assert_eq!(104 - 62 + 10, csm.shelved());
+    }
+}
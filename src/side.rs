@@ -0,0 +1,180 @@
+use crate::{card::{Card, Rank, Suit}, paytable::Paytable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// resolved from the player's first two cards and the dealer upcard, before any decisions
+pub trait SideBet {
+    // net result per unit wagered on the side bet
+    fn evaluate(&self, player: &[Card], upcard: Card) -> f64;
+}
+
+pub fn resolve(side_bets: &[&dyn SideBet], player: &[Card], upcard: Card) -> Vec<f64> {
+    side_bets
+        .iter()
+        .map(|bet| bet.evaluate(player, upcard))
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PairKind {
+    Mixed,
+    Colored,
+    Perfect,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+pub struct PerfectPairs {
+    paytable: Paytable<PairKind>,
+}
+
+impl PerfectPairs {
+    pub fn new(paytable: Paytable<PairKind>) -> Self {
+        Self { paytable }
+    }
+
+    pub fn pair_kind(player: &[Card]) -> Option<PairKind> {
+        match player {
+            [a, b] if a.rank() == b.rank() => {
+                if a.suit() == b.suit() {
+                    Some(PairKind::Perfect)
+                } else if is_red(a.suit()) == is_red(b.suit()) {
+                    Some(PairKind::Colored)
+                } else {
+                    Some(PairKind::Mixed)
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+// 6:1 mixed, 12:1 colored, 25:1 perfect
+impl Default for PerfectPairs {
+    fn default() -> Self {
+        Self::new(
+            Paytable::new(vec![(PairKind::Mixed, 6.0), (PairKind::Colored, 12.0), (PairKind::Perfect, 25.0)])
+                .expect("perfect pairs paytable is valid"),
+        )
+    }
+}
+
+impl SideBet for PerfectPairs {
+    fn evaluate(&self, player: &[Card], _upcard: Card) -> f64 {
+        match Self::pair_kind(player) {
+            Some(kind) => self.paytable.pays(&kind),
+            None => -1.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PokerHand {
+    Flush,
+    Straight,
+    ThreeOfAKind,
+    StraightFlush,
+    SuitedTrips,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+pub struct TwentyOnePlusThree {
+    paytable: Paytable<PokerHand>,
+}
+
+impl TwentyOnePlusThree {
+    pub fn new(paytable: Paytable<PokerHand>) -> Self {
+        Self { paytable }
+    }
+
+    pub fn poker_hand(cards: [Card; 3]) -> Option<PokerHand> {
+        let flush: bool = cards.iter().all(|c| c.suit() == cards[0].suit());
+        let trips: bool = cards.iter().all(|c| c.rank() == cards[0].rank());
+
+        let mut ranks: Vec<usize> = cards.iter().map(|c| c.rank() as usize).collect();
+        ranks.sort();
+
+        // aces play high as well as low
+        let straight: bool = (ranks[1] == ranks[0] + 1 && ranks[2] == ranks[1] + 1)
+            || ranks == [Rank::Ace as usize, Rank::Queen as usize, Rank::King as usize];
+
+        match (flush, trips, straight) {
+            (true, true, _) => Some(PokerHand::SuitedTrips),
+            (true, _, true) => Some(PokerHand::StraightFlush),
+            (_, true, _) => Some(PokerHand::ThreeOfAKind),
+            (_, _, true) => Some(PokerHand::Straight),
+            (true, _, _) => Some(PokerHand::Flush),
+            _ => None,
+        }
+    }
+}
+
+// 5:1 flush, 10:1 straight, 30:1 trips, 40:1 straight flush, 100:1 suited trips
+impl Default for TwentyOnePlusThree {
+    fn default() -> Self {
+        Self::new(
+            Paytable::new(vec![
+                (PokerHand::Flush, 5.0),
+                (PokerHand::Straight, 10.0),
+                (PokerHand::ThreeOfAKind, 30.0),
+                (PokerHand::StraightFlush, 40.0),
+                (PokerHand::SuitedTrips, 100.0),
+            ]).expect("21+3 paytable is valid"),
+        )
+    }
+}
+
+impl SideBet for TwentyOnePlusThree {
+    fn evaluate(&self, player: &[Card], upcard: Card) -> f64 {
+        match player {
+            [a, b] => match Self::poker_hand([*a, *b, upcard]) {
+                Some(hand) => self.paytable.pays(&hand),
+                None => -1.0,
+            },
+            _ => -1.0,
+        }
+    }
+}
+
+fn is_red(suit: Suit) -> bool {
+    matches!(suit, Suit::Diamonds | Suit::Hearts)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, side::{resolve, PairKind, PerfectPairs, SideBet, TwentyOnePlusThree}};
+
+    #[test]
+    fn built_in_side_bets() {
+        let pairs = PerfectPairs::default();
+        let plus_three = TwentyOnePlusThree::default();
+
+        let player = [Card::new(Suit::Hearts, Rank::Eight), Card::new(Suit::Diamonds, Rank::Eight)];
+        assert_eq!(Some(PairKind::Colored), PerfectPairs::pair_kind(&player));
+        assert_eq!(
+            vec![12.0, -1.0],
+            resolve(&[&pairs, &plus_three], &player, Card::new(Suit::Clubs, Rank::Two)),
+        );
+
+        let player = [Card::new(Suit::Spades, Rank::Queen), Card::new(Suit::Spades, Rank::King)];
+        assert_eq!(40.0, plus_three.evaluate(&player, Card::new(Suit::Spades, Rank::Ace)));
+        assert_eq!(10.0, plus_three.evaluate(&player, Card::new(Suit::Hearts, Rank::Jack)));
+        assert_eq!(-1.0, plus_three.evaluate(&player, Card::new(Suit::Hearts, Rank::Two)));
+    }
+
+    #[test]
+    fn custom_side_bet() {
+        // pays 3:1 whenever the dealer shows an ace
+        struct AceUp;
+
+        impl SideBet for AceUp {
+            fn evaluate(&self, _player: &[Card], upcard: Card) -> f64 {
+                if upcard.rank() == Rank::Ace { 3.0 } else { -1.0 }
+            }
+        }
+
+        let player = [Card::new(Suit::Hearts, Rank::Two), Card::new(Suit::Clubs, Rank::Nine)];
+        assert_eq!(vec![3.0], resolve(&[&AceUp], &player, Card::new(Suit::Spades, Rank::Ace)));
+    }
+}
@@ -0,0 +1,515 @@
+use std::{collections::BTreeMap, fmt};
+use crate::{card::{Card, Rank}, rule::{BlackjackPayout, RuleSet}};
+
+// chi-square critical value for 12 degrees of freedom at the 1% level
+const RANK_CHI_SQUARE_CRITICAL: f64 = 26.217;
+
+// histogram buckets are hundredths of a unit, fine enough for every payout in use
+const HISTOGRAM_SCALE: f64 = 100.0;
+
+// per-round results of a game in betting units, plus how fast it is dealt
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct HourlyProfile {
+    ev_per_round: f64,
+    sd_per_round: f64,
+    rounds_per_hour: f64,
+}
+
+impl HourlyProfile {
+    pub fn new(ev_per_round: f64, sd_per_round: f64, rounds_per_hour: f64) -> Self {
+        Self { ev_per_round, sd_per_round, rounds_per_hour }
+    }
+
+    // takes a fee of `fee_units` betting units out of every round
+    pub fn after_fee(&self, fee_units: f64) -> Self {
+        Self { ev_per_round: self.ev_per_round - fee_units, ..*self }
+    }
+
+    pub fn ev_per_round(&self) -> f64 {
+        self.ev_per_round
+    }
+
+    pub fn sd_per_round(&self) -> f64 {
+        self.sd_per_round
+    }
+
+    pub fn rounds_per_hour(&self) -> f64 {
+        self.rounds_per_hour
+    }
+
+    pub fn hourly_ev(&self) -> f64 {
+        self.ev_per_round * self.rounds_per_hour
+    }
+
+    pub fn hourly_sd(&self) -> f64 {
+        self.sd_per_round * self.rounds_per_hour.sqrt()
+    }
+
+    // factor to scale every bet by so that one hour of play has the target standard deviation
+    pub fn bet_scale_at_hourly_sd(&self, target_sd: f64) -> f64 {
+        target_sd / self.hourly_sd()
+    }
+
+    pub fn hourly_ev_at_hourly_sd(&self, target_sd: f64) -> f64 {
+        self.hourly_ev() * self.bet_scale_at_hourly_sd(target_sd)
+    }
+
+    pub fn ev_in(&self, format: &ReportFormat) -> f64 {
+        self.ev_per_round * format.rounds(self.rounds_per_hour) * format.money().unit()
+    }
+
+    pub fn sd_in(&self, format: &ReportFormat) -> f64 {
+        self.sd_per_round * format.rounds(self.rounds_per_hour).sqrt() * format.money().unit()
+    }
+
+    // e.g. "EV -$5.2 per hour, SD $120 per hour"
+    pub fn summary(&self, format: &ReportFormat) -> String {
+        format!(
+            "EV {} {}, SD {} {}",
+            format.amount(self.ev_in(format)),
+            format.rate(),
+            format.amount(self.sd_in(format)),
+            format.rate(),
+        )
+    }
+}
+
+// how many rounds an EV figure covers; one round is one hand
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum EvRate {
+    PerHand,
+    Per100Hands,
+    PerHour,
+}
+
+impl fmt::Display for EvRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PerHand => write!(f, "per hand"),
+            Self::Per100Hands => write!(f, "per 100 hands"),
+            Self::PerHour => write!(f, "per hour"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub enum Money {
+    Units,
+    // `unit` is what one betting unit is worth; the symbol goes before the amount
+    Currency { unit: f64, symbol: &'static str },
+}
+
+impl Money {
+    pub fn unit(&self) -> f64 {
+        match self {
+            Self::Units => 1.0,
+            Self::Currency { unit, .. } => *unit,
+        }
+    }
+}
+
+// how reports quote EV and SD so they line up with published figures
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct ReportFormat {
+    rate: EvRate,
+    money: Money,
+    significant_figures: u8,
+}
+
+impl ReportFormat {
+    // at least one significant figure is always printed
+    pub fn new(rate: EvRate, money: Money, significant_figures: u8) -> Self {
+        Self { rate, money, significant_figures: significant_figures.max(1) }
+    }
+
+    pub fn rate(&self) -> EvRate {
+        self.rate
+    }
+
+    pub fn money(&self) -> Money {
+        self.money
+    }
+
+    pub fn significant_figures(&self) -> u8 {
+        self.significant_figures
+    }
+
+    fn rounds(&self, rounds_per_hour: f64) -> f64 {
+        match self.rate {
+            EvRate::PerHand => 1.0,
+            EvRate::Per100Hands => 100.0,
+            EvRate::PerHour => rounds_per_hour,
+        }
+    }
+
+    // an already scaled amount, e.g. "-0.52 units" or "-$5.2"
+    pub fn amount(&self, value: f64) -> String {
+        let digits: String = significant(value.abs(), self.significant_figures);
+        let sign: &str = if value < 0.0 && digits.bytes().any(|b| matches!(b, b'1'..=b'9')) { "-" } else { "" };
+
+        match self.money {
+            Money::Units => format!("{}{} units", sign, digits),
+            Money::Currency { symbol, .. } => format!("{}{}{}", sign, symbol, digits),
+        }
+    }
+}
+
+// `value` is not negative; rounding up to the next power of ten keeps the figure count
+fn significant(value: f64, figures: u8) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{:.*}", figures as usize - 1, value);
+    }
+
+    let decimals = |v: f64| (figures as i32 - 1 - v.log10().floor() as i32).max(0);
+    let factor: f64 = 10f64.powi(figures as i32 - 1 - value.log10().floor() as i32);
+    let rounded: f64 = (value * factor).round() / factor;
+
+    format!("{:.*}", decimals(rounded) as usize, rounded)
+}
+
+// indices into `profiles` paired with their hourly EV at the target risk, best game first
+pub fn rank_at_equal_risk(profiles: &[HourlyProfile], target_sd: f64) -> Vec<(usize, f64)> {
+    let mut ranked: Vec<(usize, f64)> = profiles
+        .iter()
+        .map(|p| p.hourly_ev_at_hourly_sd(target_sd))
+        .enumerate()
+        .collect();
+
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranked
+}
+
+// how often each rank came out of the shoe, for checking custom rngs and shuffles for bias
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+pub struct RankTally {
+    counts: [u64; 13],
+}
+
+impl RankTally {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, card: Card) {
+        self.counts[card.rank() as usize] += 1;
+    }
+
+    pub fn merge(&mut self, other: &RankTally) {
+        for (count, n) in self.counts.iter_mut().zip(other.counts) {
+            *count += n;
+        }
+    }
+
+    pub fn count(&self, rank: Rank) -> u64 {
+        self.counts[rank as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn frequency(&self, rank: Rank) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.count(rank) as f64 / total as f64,
+        }
+    }
+
+    // every full deck holds each rank equally often
+    pub fn chi_square(&self) -> f64 {
+        let expected: f64 = self.total() as f64 / 13.0;
+
+        if expected == 0.0 {
+            return 0.0;
+        }
+
+        self.counts
+            .iter()
+            .map(|&n| (n as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    pub fn is_biased(&self) -> bool {
+        self.chi_square() > RANK_CHI_SQUARE_CRITICAL
+    }
+}
+
+impl Extend<Card> for RankTally {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, cards: I) {
+        for card in cards {
+            self.record(card);
+        }
+    }
+}
+
+// chances of naturals off the top of a shoe, player dealt first
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+pub struct NaturalFrequencies {
+    player: f64,
+    dealer: f64,
+    both: f64,
+}
+
+impl NaturalFrequencies {
+    // `counts` holds the cards of each rank left in the shoe, indexed by rank
+    pub fn from_counts(counts: &[u64; 13]) -> Self {
+        let n: f64 = counts.iter().sum::<u64>() as f64;
+        let aces: f64 = counts[Rank::Ace as usize] as f64;
+        let tens: f64 = counts[Rank::Ten as usize..].iter().sum::<u64>() as f64;
+
+        if n < 4.0 {
+            return Self { player: 0.0, dealer: 0.0, both: 0.0 };
+        }
+
+        let natural: f64 = 2.0 * aces * tens / (n * (n - 1.0));
+        let after_natural: f64 = 2.0 * (aces - 1.0).max(0.0) * (tens - 1.0).max(0.0) / ((n - 2.0) * (n - 3.0));
+
+        Self { player: natural, dealer: natural, both: natural * after_natural }
+    }
+
+    pub fn for_rules(rules: &RuleSet) -> Self {
+        let counts: [u64; 13] = [4 * rules.decks() as u64; 13];
+
+        #[cfg(feature = "variant-spanish21")]
+        let counts: [u64; 13] = if rules.spanish_21() {
+            let mut stripped: [u64; 13] = counts;
+            stripped[Rank::Ten as usize] = 0;
+            stripped
+        } else {
+            counts
+        };
+
+        Self::from_counts(&counts)
+    }
+
+    pub fn player(&self) -> f64 {
+        self.player
+    }
+
+    pub fn dealer(&self) -> f64 {
+        self.dealer
+    }
+
+    pub fn both(&self) -> f64 {
+        self.both
+    }
+
+    // what naturals are worth per hand at the given payout; pushes against dealer naturals pay nothing
+    pub fn payout_value(&self, payout: BlackjackPayout) -> f64 {
+        (self.player - self.both) * payout.ratio()
+    }
+}
+
+// net result per round of a simulation run; runs from different machines or seeds merge exactly
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SimReport {
+    rounds: u64,
+    mean: f64,
+    // sum of squared deviations from the mean
+    m2: f64,
+    histogram: BTreeMap<i64, u64>,
+}
+
+impl SimReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, net_units: f64) {
+        self.rounds += 1;
+
+        let delta: f64 = net_units - self.mean;
+        self.mean += delta / self.rounds as f64;
+        self.m2 += delta * (net_units - self.mean);
+
+        *self.histogram.entry((net_units * HISTOGRAM_SCALE).round() as i64).or_insert(0) += 1;
+    }
+
+    // pairwise combination of the moments, so the result matches one run over every round
+    pub fn merge(reports: &[SimReport]) -> Self {
+        let mut merged = Self::new();
+
+        for report in reports.iter().filter(|r| r.rounds > 0) {
+            let rounds: u64 = merged.rounds + report.rounds;
+            let delta: f64 = report.mean - merged.mean;
+
+            merged.mean += delta * report.rounds as f64 / rounds as f64;
+            merged.m2 += report.m2 + delta * delta * merged.rounds as f64 * report.rounds as f64 / rounds as f64;
+            merged.rounds = rounds;
+
+            for (&bucket, &n) in &report.histogram {
+                *merged.histogram.entry(bucket).or_insert(0) += n;
+            }
+        }
+
+        merged
+    }
+
+    pub fn rounds(&self) -> u64 {
+        self.rounds
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    // sample variance
+    pub fn variance(&self) -> f64 {
+        match self.rounds {
+            0 | 1 => 0.0,
+            n => self.m2 / (n - 1) as f64,
+        }
+    }
+
+    pub fn sd(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    pub fn standard_error(&self) -> f64 {
+        match self.rounds {
+            0 => 0.0,
+            n => self.sd() / (n as f64).sqrt(),
+        }
+    }
+
+    // `z` is the normal quantile, e.g. 1.96 for 95%
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        let margin: f64 = z * self.standard_error();
+
+        (self.mean - margin, self.mean + margin)
+    }
+
+    // rounds per net result, lowest result first
+    pub fn histogram(&self) -> Vec<(f64, u64)> {
+        self.histogram
+            .iter()
+            .map(|(&bucket, &n)| (bucket as f64 / HISTOGRAM_SCALE, n))
+            .collect()
+    }
+
+    pub fn profile(&self, rounds_per_hour: f64) -> HourlyProfile {
+        HourlyProfile::new(self.mean, self.sd(), rounds_per_hour)
+    }
+}
+
+impl Extend<f64> for SimReport {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, results: I) {
+        for net_units in results {
+            self.record(net_units);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{card::{Card, Rank, Suit}, deck::Deck, rule::{BlackjackPayout, RoundFee, RuleSet}, stats::{rank_at_equal_risk, EvRate, HourlyProfile, Money, NaturalFrequencies, RankTally, ReportFormat, SimReport}};
+
+    #[test]
+    fn equal_risk_comparison() {
+        let slow = HourlyProfile::new(0.01, 1.15, 100.0);
+        assert!((slow.hourly_ev() - 1.0).abs() < 1e-9);
+        assert!((slow.hourly_sd() - 11.5).abs() < 1e-9);
+        assert!((slow.hourly_ev_at_hourly_sd(23.0) - 2.0).abs() < 1e-9);
+
+        // same edge dealt four times as fast only doubles the risk-adjusted win rate
+        let fast = HourlyProfile::new(0.01, 1.15, 400.0);
+        let noisy = HourlyProfile::new(0.012, 2.0, 100.0);
+
+        let ranked = rank_at_equal_risk(&[slow, fast, noisy], 23.0);
+        assert_eq!(vec![1, 0, 2], ranked.iter().map(|r| r.0).collect::<Vec<usize>>());
+        assert!((ranked[0].1 - 4.0).abs() < 1e-9);
+
+        // a 1-unit ante on a 25-unit bet
+        let rules = RuleSet::builder().round_fee(Some(RoundFee::Flat(1.0))).build().unwrap();
+        let fee: f64 = rules.round_fee().unwrap().in_units(25.0);
+        assert!((slow.after_fee(fee).hourly_ev() + 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rank_bias() {
+        let mut deck = Deck::new_shoe(6);
+        deck.shuffle();
+
+        let mut tally = RankTally::new();
+        while let Some(card) = deck.draw() {
+            tally.record(card);
+        }
+
+        assert_eq!(312, tally.total());
+        assert_eq!(24, tally.count(Rank::Ace));
+        assert_eq!(0.0, tally.chi_square());
+        assert!(!tally.is_biased());
+
+        // a shuffle that leaks extra tens
+        let mut heavy = RankTally::new();
+        heavy.extend(vec![Card::new(Suit::Spades, Rank::Ten); 40]);
+        tally.merge(&heavy);
+
+        assert_eq!(64, tally.count(Rank::Ten));
+        assert!(tally.is_biased());
+    }
+
+    #[test]
+    fn natural_frequencies() {
+        let single = NaturalFrequencies::for_rules(&RuleSet::single_deck_downtown());
+        assert!((single.player() - 32.0 / 663.0).abs() < 1e-12);
+        assert!((single.both() - 32.0 / 663.0 * 2.0 * 3.0 * 15.0 / (50.0 * 49.0)).abs() < 1e-12);
+
+        // 6:5 gives back about 1.4% of each hand against 3:2 in a six deck shoe
+        let six = NaturalFrequencies::for_rules(&RuleSet::builder().build().unwrap());
+        let cost = six.payout_value(BlackjackPayout::ThreeToTwo) - six.payout_value(BlackjackPayout::SixToFive);
+        assert!((cost - 0.0136).abs() < 0.0005);
+
+        #[cfg(feature = "variant-spanish21")]
+        {
+            let spanish = NaturalFrequencies::for_rules(&RuleSet::builder().spanish_21(true).build().unwrap());
+            assert!(spanish.player() < six.player());
+        }
+    }
+
+    #[test]
+    fn merged_reports() {
+        let results: Vec<f64> = vec![1.0, -1.0, 1.5, 0.0, -1.0, 2.0, -2.0, -0.5, 1.0, -1.0];
+
+        let mut whole = SimReport::new();
+        whole.extend(results.iter().copied());
+
+        let mut first = SimReport::new();
+        first.extend(results[..3].iter().copied());
+        let mut second = SimReport::new();
+        second.extend(results[3..].iter().copied());
+
+        let merged = SimReport::merge(&[first, SimReport::new(), second]);
+        assert_eq!(whole.rounds(), merged.rounds());
+        assert!((whole.mean() - merged.mean()).abs() < 1e-12);
+        assert!((whole.variance() - merged.variance()).abs() < 1e-12);
+        assert_eq!(whole.histogram(), merged.histogram());
+        assert_eq!((-1.0, 3), merged.histogram()[1]);
+
+        let (low, high) = merged.confidence_interval(1.96);
+        assert!(low < merged.mean() && merged.mean() < high);
+        assert!((high - merged.mean() - 1.96 * merged.sd() / 10f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn report_formats() {
+        let mut report = SimReport::new();
+        report.extend([1.0, -1.0, 1.0, -1.0, 1.5, -1.0, 0.0, -1.0]);
+        let profile = report.profile(80.0);
+
+        let per_100 = ReportFormat::new(EvRate::Per100Hands, Money::Units, 3);
+        assert!((profile.ev_in(&per_100) + 6.25).abs() < 1e-9);
+        assert_eq!("EV -6.25 units per 100 hands, SD 10.8 units per 100 hands", profile.summary(&per_100));
+
+        let hourly = ReportFormat::new(EvRate::PerHour, Money::Currency { unit: 10.0, symbol: "$" }, 2);
+        assert_eq!("EV -$50 per hour, SD $97 per hour", profile.summary(&hourly));
+
+        let per_hand = ReportFormat::new(EvRate::PerHand, Money::Units, 2);
+        assert_eq!("0.0010 units", per_hand.amount(0.001));
+        assert_eq!("10 units", per_hand.amount(9.96));
+        assert_eq!("0.0 units", per_hand.amount(-0.0));
+        assert_eq!("-1500 units", per_hand.amount(-1534.0));
+        assert_eq!(1, ReportFormat::new(EvRate::PerHand, Money::Units, 0).significant_figures());
+    }
+}
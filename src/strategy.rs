@@ -0,0 +1,282 @@
+use std::{fmt, io::{self, BufRead, StdinLock, Stdout, Write}};
+use crate::{card::{Card, Rank, Suit}, encoding::Encoding, hand::Hand, play::{self, Action, Context, Strategy}, rule::RuleSet};
+
+// chart columns, in the order basic strategy charts print them
+const UPCARDS: [Rank; 10] = [
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Ace,
+];
+
+// upcards in `Encoding::V1` one-hot order
+const V1_UPCARDS: [Rank; 10] = [
+    Rank::Ace,
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+];
+
+// a chart row, each played from a representative two-card hand
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum GridRow {
+    // 5 through 19; hard 20 is only dealt as a pair of tens
+    Hard(u8),
+    // ace plus 2 through 9
+    Soft(u8),
+    Pair(Rank),
+}
+
+impl GridRow {
+    fn cards(&self) -> [Rank; 2] {
+        match *self {
+            Self::Hard(total) if total <= 11 => [Rank::Two, rank_of(total - 2)],
+            Self::Hard(total) => [rank_of(total - 10), Rank::Ten],
+            Self::Soft(total) => [Rank::Ace, rank_of(total - 11)],
+            Self::Pair(rank) => [rank, rank],
+        }
+    }
+}
+
+impl fmt::Display for GridRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hard(total) => write!(f, "{}", total),
+            Self::Soft(total) => write!(f, "A,{}", total - 11),
+            Self::Pair(rank) => write!(f, "{},{}", rank, rank),
+        }
+    }
+}
+
+// the card worth `value`, tens for 10
+fn rank_of(value: u8) -> Rank {
+    UPCARDS[(value as usize + 8) % 10]
+}
+
+// what a strategy plays in every chart cell, one column per upcard from 2 to ace
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ActionGrid {
+    rows: Vec<(GridRow, [Action; 10])>,
+}
+
+impl ActionGrid {
+    pub fn rows(&self) -> &[(GridRow, [Action; 10])] {
+        &self.rows
+    }
+
+    pub fn get(&self, row: GridRow, upcard: Rank) -> Option<Action> {
+        let column: usize = UPCARDS.iter().position(|&r| r == column_rank(upcard))?;
+
+        self.rows.iter().find(|(r, _)| *r == row).map(|(_, actions)| actions[column])
+    }
+
+    // cells where the two grids disagree, as (row, upcard, this action, other action)
+    pub fn diff(&self, other: &ActionGrid) -> Vec<(GridRow, Rank, Action, Action)> {
+        let mut differences: Vec<(GridRow, Rank, Action, Action)> = Vec::new();
+
+        for ((row, ours), (_, theirs)) in self.rows.iter().zip(&other.rows) {
+            for (i, &upcard) in UPCARDS.iter().enumerate() {
+                if ours[i] != theirs[i] {
+                    differences.push((*row, upcard, ours[i], theirs[i]));
+                }
+            }
+        }
+
+        differences
+    }
+}
+
+impl fmt::Display for ActionGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "    ")?;
+        for upcard in UPCARDS {
+            write!(f, " {}", upcard)?;
+        }
+
+        for (row, actions) in &self.rows {
+            write!(f, "\n{:>4}", row.to_string())?;
+            for action in actions {
+                write!(f, " {}", action)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn column_rank(upcard: Rank) -> Rank {
+    match upcard {
+        Rank::Jack | Rank::Queen | Rank::King => Rank::Ten,
+        rank => rank,
+    }
+}
+
+// runs the strategy over every chart cell off the top of a neutral shoe, for diffing against a known chart
+pub fn evaluate_grid<T>(strategy: &mut T, rules: &RuleSet) -> ActionGrid
+    where T: Strategy
+{
+    let rows = (5..=19).map(GridRow::Hard)
+        .chain((13..=20).map(GridRow::Soft))
+        .chain(UPCARDS.into_iter().map(GridRow::Pair));
+
+    let rows: Vec<(GridRow, [Action; 10])> = rows
+        .map(|row| {
+            let [first, second] = row.cards();
+            let hand = Hand::new([Card::new(Suit::Spades, first), Card::new(Suit::Hearts, second)]);
+
+            let actions: [Action; 10] = UPCARDS.map(|upcard| {
+                let context = Context::new(Card::new(Suit::Clubs, upcard), 1);
+
+                play::decide(strategy, Encoding::V1, &hand, rules, &context, 0.0, 0.0)
+            });
+
+            (row, actions)
+        })
+        .collect();
+
+    ActionGrid { rows }
+}
+
+// a person playing at the terminal; expects `Encoding::V1` observations and stands once the input is closed
+#[derive(Debug)]
+pub struct Console<R, W> {
+    input: R,
+    output: W,
+}
+
+impl Console<StdinLock<'static>, Stdout> {
+    pub fn stdin() -> Self {
+        Self::new(io::stdin().lock(), io::stdout())
+    }
+}
+
+impl<R, W> Console<R, W>
+    where R: BufRead, W: Write
+{
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.input, self.output)
+    }
+
+    // asks until a legal code is typed; `None` once the input runs out
+    fn prompt(&mut self, observation: &[f32], legal: &[Action]) -> io::Result<Option<Action>> {
+        writeln!(self.output, "{}", describe(observation))?;
+
+        let codes: Vec<&str> = legal.iter().map(Action::code).collect();
+        let mut line = String::new();
+
+        loop {
+            write!(self.output, "{}? ", codes.join("/"))?;
+            self.output.flush()?;
+
+            line.clear();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+
+            match line.trim().to_uppercase().parse::<Action>() {
+                Ok(action) if legal.contains(&action) => return Ok(Some(action)),
+                _ => writeln!(self.output, "{} is not one of {}", line.trim(), codes.join(", "))?,
+            }
+        }
+    }
+}
+
+impl<R, W> Strategy for Console<R, W>
+    where R: BufRead, W: Write
+{
+    fn decide(&mut self, observation: &[f32], legal: &[Action]) -> Action {
+        self.prompt(observation, legal).ok().flatten().unwrap_or(Action::Stand)
+    }
+}
+
+// e.g. "soft 17 against 6, true count +1.0"
+fn describe(observation: &[f32]) -> String {
+    let total: u8 = (observation[0] * 21.0).round() as u8;
+    let kind: &str = if observation[1] > 0.5 { "soft" } else { "hard" };
+    let pair: &str = if observation[2] > 0.5 { " (pair)" } else { "" };
+    let upcard: Rank = observation[3..13]
+        .iter()
+        .position(|&f| f > 0.5)
+        .map_or(Rank::Ten, |i| V1_UPCARDS[i]);
+
+    format!("{} {}{} against {}, true count {:+.1}", kind, total, pair, upcard, observation[13])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::{card::{Card, Rank, Suit}, encoding::Encoding, hand::Hand, play::{decide, Action, Context}, rule::RuleSet, strategy::{evaluate_grid, Console, GridRow}};
+
+    #[test]
+    fn console_reads_until_legal() {
+        let rules = RuleSet::builder().build().unwrap();
+        let hand = Hand::new([Card::new(Suit::Spades, Rank::Ace), Card::new(Suit::Hearts, Rank::Six)]);
+        let context = Context::new(Card::new(Suit::Clubs, Rank::Six), 1);
+
+        let mut console = Console::new(Cursor::new("q\np\nd\n"), Vec::new());
+        assert_eq!(Action::DoubleDown, decide(&mut console, Encoding::V1, &hand, &rules, &context, 1.0, 0.25));
+
+        let (_, output) = console.into_inner();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("soft 17 against 6, true count +1.0\nH/S/D? "));
+        assert!(output.contains("q is not one of H, S, D"));
+        assert!(output.contains("p is not one of H, S, D"));
+    }
+
+    #[test]
+    fn closed_console_stands() {
+        let rules = RuleSet::builder().build().unwrap();
+        let hand = Hand::new([Card::new(Suit::Spades, Rank::Eight), Card::new(Suit::Hearts, Rank::Eight)]);
+        let context = Context::new(Card::new(Suit::Clubs, Rank::King), 1);
+
+        let mut console = Console::new(Cursor::new(""), Vec::new());
+        assert_eq!(Action::Stand, decide(&mut console, Encoding::V1, &hand, &rules, &context, -2.0, 0.5));
+
+        let (_, output) = console.into_inner();
+        assert!(String::from_utf8(output).unwrap().starts_with("hard 16 (pair) against T, true count -2.0\n"));
+    }
+
+    #[test]
+    fn grid_covers_every_chart_cell() {
+        let rules = RuleSet::builder().build().unwrap();
+
+        // stands on 17 or more, soft or hard
+        let mut stand_on_17 = |observation: &[f32], _: &[Action]| {
+            if observation[0] * 21.0 >= 16.5 { Action::Stand } else { Action::Hit }
+        };
+        let grid = evaluate_grid(&mut stand_on_17, &rules);
+
+        assert_eq!(15 + 8 + 10, grid.rows().len());
+        assert_eq!(Some(Action::Hit), grid.get(GridRow::Hard(16), Rank::Ten));
+        assert_eq!(Some(Action::Stand), grid.get(GridRow::Soft(18), Rank::King));
+        assert_eq!(Some(Action::Hit), grid.get(GridRow::Pair(Rank::Eight), Rank::Ace));
+        assert_eq!(Some(Action::Stand), grid.get(GridRow::Pair(Rank::Ten), Rank::Two));
+        assert_eq!(None, grid.get(GridRow::Hard(20), Rank::Two));
+
+        let chart = grid.to_string();
+        assert!(chart.starts_with("     2 3 4 5 6 7 8 9 T A\n   5 H H"));
+        assert!(chart.contains("\n A,7 S S S S S S S S S S\n"));
+        assert!(chart.ends_with("\n A,A H H H H H H H H H H"));
+
+        let mut always_hit = |_: &[f32], _: &[Action]| Action::Hit;
+        let differences = grid.diff(&evaluate_grid(&mut always_hit, &rules));
+        assert_eq!(3 * 10 + 4 * 10 + 2 * 10, differences.len());
+        assert!(differences.contains(&(GridRow::Hard(17), Rank::Ace, Action::Stand, Action::Hit)));
+    }
+}